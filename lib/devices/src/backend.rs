@@ -0,0 +1,420 @@
+//! Device hardware access
+//! devices read and switch the hardware through `DeviceBackend`,
+//! transports implement it for real devices,
+//! `SimBackend` is a deterministic simulation for tests.
+//! Backend is shared, so device clones control the same hardware.
+use crate::units::{Power, TempDelta, Temperature};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Hardware access error
+///
+/// -`Offline`      - device does not answer at all
+/// -`Timeout`      - request is not answered in time
+/// -`Unsupported`  - device have no such function
+/// -`Transport`    - transport specific error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendError {
+    Offline,
+    Timeout,
+    Unsupported,
+    Transport(String),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackendError::Offline => fmt.write_str("device is offline"),
+            BackendError::Timeout => fmt.write_str("device request timed out"),
+            BackendError::Unsupported => fmt.write_str("device function is not supported"),
+            BackendError::Transport(err) => write!(fmt, "device transport error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Hardware of one device, functions not present are `Unsupported`
+pub trait DeviceBackend: Send {
+    /// Switch the relay
+    /// -`enable`   - new state
+    fn switch(&mut self, enable: bool) -> Result<(), BackendError> {
+        let _ = enable;
+        Err(BackendError::Unsupported)
+    }
+
+    /// Read relay state, it may be switched by hand
+    fn enabled(&mut self) -> Result<bool, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    /// Read power consumption
+    fn power(&mut self) -> Result<Power, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    /// Read temperature
+    fn temperature(&mut self) -> Result<Temperature, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+}
+
+/// Backend handle, shared by device and its clones
+pub type SharedBackend = Arc<Mutex<dyn DeviceBackend>>;
+
+/// Share backend
+/// -`backend`  - hardware access
+///
+/// -`return`   - backend handle for devices
+pub fn shared<B: DeviceBackend + 'static>(backend: B) -> SharedBackend {
+    Arc::new(Mutex::new(backend))
+}
+
+/// Backend of new devices, until the real one is set
+pub(crate) fn simulated() -> SharedBackend {
+    shared(SimBackend::default())
+}
+
+/// Run request on shared backend, poisoned lock is taken as is
+pub(crate) fn with<R>(
+    backend: &SharedBackend,
+    request: impl FnOnce(&mut dyn DeviceBackend) -> Result<R, BackendError>,
+) -> Result<R, BackendError> {
+    let mut backend = backend.lock().unwrap_or_else(|err| err.into_inner());
+    request(&mut *backend)
+}
+
+/// Simulated power consumption, one point per reading
+///
+/// -`Constant` - same power
+/// -`Cycle`    - points are repeated
+/// -`Ramp`     - linear change over `steps` readings, then stays at `to`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadProfile {
+    Constant(Power),
+    Cycle(Vec<Power>),
+    Ramp { from: Power, to: Power, steps: u64 },
+}
+
+impl LoadProfile {
+    /// Power of the reading
+    fn at(&self, tick: u64) -> Power {
+        match self {
+            LoadProfile::Constant(power) => *power,
+            LoadProfile::Cycle(points) if points.is_empty() => Power::ZERO,
+            LoadProfile::Cycle(points) => points[(tick % points.len() as u64) as usize],
+            LoadProfile::Ramp { from, to, steps } => {
+                let step: u64 = tick.min(*steps);
+                let (from, to) = (from.milliwatts() as i128, to.milliwatts() as i128);
+                let mw: i128 = from + (to - from) * step as i128 / (*steps).max(1) as i128;
+                Power::from_milliwatts(mw as u64)
+            }
+        }
+    }
+}
+
+/// Simulated temperature, one point per reading
+///
+/// -`Constant` - same temperature
+/// -`Cycle`    - points are repeated
+/// -`Wave`     - triangle wave around `mean`, `period` readings long
+#[derive(Debug, Clone, PartialEq)]
+pub enum TempCurve {
+    Constant(Temperature),
+    Cycle(Vec<Temperature>),
+    Wave {
+        mean: Temperature,
+        amplitude: TempDelta,
+        period: u64,
+    },
+}
+
+impl TempCurve {
+    /// Temperature of the reading
+    fn at(&self, tick: u64) -> Temperature {
+        match self {
+            TempCurve::Constant(temp) => *temp,
+            TempCurve::Cycle(points) if points.is_empty() => Temperature::ABSOLUTE_ZERO,
+            TempCurve::Cycle(points) => points[(tick % points.len() as u64) as usize],
+            TempCurve::Wave {
+                mean,
+                amplitude,
+                period,
+            } => {
+                /* phase goes 0..period, wave goes -1..1..-1 */
+                let period: i64 = (*period).max(1) as i64;
+                let phase: i64 = (tick % period as u64) as i64;
+                let wave: i64 = (4 * phase - 2 * period).abs() - period;
+                let mk: i64 = amplitude.millikelvin() * -wave / period;
+                mean.checked_add(TempDelta::from_millikelvin(mk))
+                    .unwrap_or(Temperature::ABSOLUTE_ZERO)
+            }
+        }
+    }
+}
+
+/// Deterministic device simulation,
+/// same seed and requests give same readings
+///
+/// -`enabled`      - relay state
+/// -`load`         - power consumption, when enabled
+/// -`temp`         - temperature curve
+/// -`noise_mw`     - max power noise, mW
+/// -`noise_mk`     - max temperature noise, mK
+/// -`fail_every`   - every n-th request times out, 0 is never
+/// -`offline`      - all requests fail
+/// -`tick`         - readings made
+/// -`requests`     - requests made
+/// -`rng`          - noise generator state
+#[derive(Debug, Clone)]
+pub struct SimBackend {
+    enabled: bool,
+    load: LoadProfile,
+    temp: TempCurve,
+    noise_mw: u64,
+    noise_mk: u64,
+    fail_every: u64,
+    offline: bool,
+    tick: u64,
+    requests: u64,
+    rng: u64,
+}
+
+impl SimBackend {
+    /// Simulation ctor, 10 W load and 20 °C without noise and failures
+    /// -`seed`     - noise seed
+    pub fn new(seed: u64) -> Self {
+        SimBackend {
+            enabled: false,
+            load: LoadProfile::Constant(Power::from_milliwatts(10000)),
+            temp: TempCurve::Constant(
+                Temperature::from_celsius(20.0).unwrap_or(Temperature::ZERO_CELSIUS),
+            ),
+            noise_mw: 0,
+            noise_mk: 0,
+            fail_every: 0,
+            offline: false,
+            tick: 0,
+            requests: 0,
+            /* xorshift state may not be zero */
+            rng: if 0 == seed {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Set power consumption profile
+    pub fn with_load(mut self, load: LoadProfile) -> Self {
+        self.load = load;
+        self
+    }
+
+    /// Set temperature curve
+    pub fn with_temp(mut self, temp: TempCurve) -> Self {
+        self.temp = temp;
+        self
+    }
+
+    /// Set max noise, added to readings
+    /// -`power`    - max power noise
+    /// -`temp`     - max temperature noise
+    pub fn with_noise(mut self, power: Power, temp: TempDelta) -> Self {
+        self.noise_mw = power.milliwatts();
+        self.noise_mk = temp.millikelvin().unsigned_abs();
+        self
+    }
+
+    /// Make every n-th request time out
+    /// -`every`    - failure period, 0 is never
+    pub fn with_failures(mut self, every: u64) -> Self {
+        self.fail_every = every;
+        self
+    }
+
+    /// Take device offline or back online
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// Switch relay by hand, device is not informed
+    pub fn press(&mut self, enable: bool) {
+        self.enabled = enable;
+    }
+
+    /// Count request, fail it, if it is planned
+    fn request(&mut self) -> Result<(), BackendError> {
+        self.requests += 1;
+        if self.offline {
+            return Err(BackendError::Offline);
+        }
+        if 0 != self.fail_every && self.requests.is_multiple_of(self.fail_every) {
+            return Err(BackendError::Timeout);
+        }
+        Ok(())
+    }
+
+    /// Noise in -max..=max
+    fn noise(&mut self, max: u64) -> i64 {
+        /* xorshift64 */
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        if 0 == max {
+            return 0;
+        }
+        let max: i64 = max.min(i64::MAX as u64 / 2) as i64;
+        (self.rng % (2 * max as u64 + 1)) as i64 - max
+    }
+}
+
+impl Default for SimBackend {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl DeviceBackend for SimBackend {
+    fn switch(&mut self, enable: bool) -> Result<(), BackendError> {
+        self.request()?;
+        self.enabled = enable;
+        Ok(())
+    }
+
+    fn enabled(&mut self) -> Result<bool, BackendError> {
+        self.request()?;
+        Ok(self.enabled)
+    }
+
+    fn power(&mut self) -> Result<Power, BackendError> {
+        self.request()?;
+        if !self.enabled {
+            return Ok(Power::ZERO);
+        }
+        let power: Power = self.load.at(self.tick);
+        self.tick += 1;
+        let noise: i64 = self.noise(self.noise_mw);
+        Ok(Power::from_milliwatts(
+            power.milliwatts().saturating_add_signed(noise),
+        ))
+    }
+
+    fn temperature(&mut self) -> Result<Temperature, BackendError> {
+        self.request()?;
+        let temp: Temperature = self.temp.at(self.tick);
+        self.tick += 1;
+        let noise: i64 = self.noise(self.noise_mk);
+        Ok(temp
+            .checked_add(TempDelta::from_millikelvin(noise))
+            .unwrap_or(Temperature::ABSOLUTE_ZERO))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{self, BackendError, DeviceBackend, LoadProfile, SimBackend, TempCurve};
+    use crate::units::{Power, TempDelta, Temperature};
+    use crate::SmartSocket;
+    use crate::SmartThermometer;
+    use std::sync::{Arc, Mutex};
+
+    fn watts(watts: u64) -> Power {
+        Power::from_milliwatts(watts * 1000)
+    }
+
+    #[test]
+    fn sim_socket_tests() {
+        let load: LoadProfile = LoadProfile::Cycle(vec![watts(100), watts(2000)]);
+        let sim: Arc<Mutex<SimBackend>> = Arc::new(Mutex::new(SimBackend::new(7).with_load(load)));
+        let mut socket: SmartSocket = SmartSocket::with_backend("kettle", sim.clone());
+
+        socket.en();
+        socket.update();
+        assert_eq!(watts(100), socket.power);
+        socket.update();
+        assert_eq!(watts(2000), socket.power);
+
+        /* switched by hand, socket sees it on update */
+        sim.lock().unwrap().press(false);
+        socket.update();
+        assert!(!socket.enabled);
+        assert_eq!(Power::ZERO, socket.power);
+
+        /* failed request keeps the state */
+        sim.lock().unwrap().set_offline(true);
+        assert_eq!(Err(BackendError::Offline), socket.try_en());
+        socket.en();
+        assert!(!socket.enabled);
+        sim.lock().unwrap().set_offline(false);
+        assert_eq!(Ok(()), socket.try_en());
+        assert!(socket.enabled);
+
+        /* clone controls the same hardware */
+        let mut copy: SmartSocket = socket.clone();
+        copy.dis();
+        socket.update();
+        assert!(!socket.enabled);
+    }
+
+    #[test]
+    fn sim_thermometer_tests() {
+        let mean: Temperature = Temperature::from_celsius(20.0).unwrap();
+        let curve: TempCurve = TempCurve::Wave {
+            mean,
+            amplitude: TempDelta::from_kelvin(4.0),
+            period: 4,
+        };
+        let readings = |seed: u64, noise: TempDelta| -> Vec<i64> {
+            let sim: SimBackend = SimBackend::new(seed)
+                .with_temp(curve.clone())
+                .with_noise(Power::ZERO, noise);
+            let mut thermometer: SmartThermometer =
+                SmartThermometer::with_backend("outdoor", backend::shared(sim));
+            (0..8)
+                .map(|_| {
+                    thermometer.update();
+                    thermometer.temp.delta(mean).millikelvin()
+                })
+                .collect()
+        };
+        let clean: Vec<i64> = readings(1, TempDelta::default());
+        assert_eq!(vec![-4000, 0, 4000, 0, -4000, 0, 4000, 0], clean);
+
+        /* noise is bounded and reproducible */
+        let noisy: Vec<i64> = readings(1, TempDelta::from_kelvin(0.5));
+        assert_eq!(noisy, readings(1, TempDelta::from_kelvin(0.5)));
+        assert_ne!(noisy, readings(2, TempDelta::from_kelvin(0.5)));
+        assert!(noisy.iter().zip(clean).all(|(n, c)| (n - c).abs() <= 500));
+
+        /* every third request times out, last reading is kept */
+        let sim: SimBackend = SimBackend::new(0)
+            .with_temp(TempCurve::Cycle(vec![mean, Temperature::ZERO_CELSIUS]))
+            .with_failures(3);
+        let mut thermometer: SmartThermometer =
+            SmartThermometer::with_backend("outdoor", backend::shared(sim));
+        assert_eq!(Ok(()), thermometer.try_update());
+        assert_eq!(Ok(()), thermometer.try_update());
+        assert_eq!(Err(BackendError::Timeout), thermometer.try_update());
+        assert_eq!(Temperature::ZERO_CELSIUS, thermometer.temp);
+
+        /* backend without relay */
+        struct Probe;
+        impl DeviceBackend for Probe {}
+        let mut socket: SmartSocket = SmartSocket::with_backend("probe", backend::shared(Probe));
+        assert_eq!(Err(BackendError::Unsupported), socket.try_en());
+    }
+
+    #[test]
+    fn load_profile_tests() {
+        let ramp: LoadProfile = LoadProfile::Ramp {
+            from: watts(10),
+            to: watts(0),
+            steps: 5,
+        };
+        let points: Vec<u64> = (0..7)
+            .map(|tick| ramp.at(tick).milliwatts() / 1000)
+            .collect();
+        assert_eq!(vec![10, 8, 6, 4, 2, 0, 0], points);
+    }
+}
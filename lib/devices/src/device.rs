@@ -0,0 +1,233 @@
+//! Device interface
+//! every smarthome device type implements `Device`,
+//! so new device types can live in other crates
+//! and still be owned by `SmartDevice`
+use crate::id::DeviceId;
+use crate::status::{DeviceState, DeviceStatus};
+use std::any::Any;
+use std::fmt;
+use std::ops::BitOr;
+
+/// Set of device capabilities
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// No capabilities
+    pub const NONE: Self = Capabilities(0);
+    /// Device can be switched on and off
+    pub const SWITCH: Self = Capabilities(1 << 0);
+    /// Device measures power consumption
+    pub const POWER: Self = Capabilities(1 << 1);
+    /// Device measures temperature
+    pub const TEMPERATURE: Self = Capabilities(1 << 2);
+
+    /// Return true, if all capabilities of other are present
+    pub const fn contains(self, other: Self) -> bool {
+        other.0 == self.0 & other.0
+    }
+
+    /// Join two sets
+    pub const fn union(self, other: Self) -> Self {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+/// Smarthome device interface
+pub trait Device: DeviceClone + Any {
+    /// Get device id, it is unique for every device
+    /// -`return` device id
+    fn id(&self) -> DeviceId;
+
+    /// Get device name without copying it
+    /// -`return` device name
+    fn name_str(&self) -> &str;
+
+    /// Get device name
+    /// -`return` device name
+    fn name(&self) -> String {
+        self.name_str().to_string()
+    }
+
+    /// Get device kind, short type name, like "socket"
+    /// -`return` device kind
+    fn kind(&self) -> &'static str;
+
+    /// Get structured device status
+    /// -`return` device status, taken now
+    fn report(&self) -> DeviceStatus;
+
+    /// Get device status
+    /// -`return` human readable device status
+    fn status(&self) -> String {
+        self.report().to_string()
+    }
+
+    /// Update device status
+    fn update(&mut self);
+
+    /// Get device capabilities
+    /// -`return` capabilities set
+    fn capabilities(&self) -> Capabilities;
+
+    /// Switch device on or off
+    /// -`enable`   - new state
+    ///
+    /// -`return`   - true, if device can be switched
+    fn switch(&mut self, enable: bool) -> bool {
+        let _ = enable;
+        false
+    }
+}
+
+/// Boxed copy of device, implemented for every `Clone` device,
+/// copy should take its own id
+pub trait DeviceClone {
+    /// Copy device into new box
+    /// -`return` device copy
+    fn clone_box(&self) -> Box<dyn Device>;
+}
+
+impl<T: Device + Clone> DeviceClone for T {
+    fn clone_box(&self) -> Box<dyn Device> {
+        Box::new(self.clone())
+    }
+}
+
+/// Not a device, placeholder for empty device slot
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Nodev;
+
+impl Device for Nodev {
+    fn id(&self) -> DeviceId {
+        DeviceId::NONE
+    }
+
+    fn name_str(&self) -> &str {
+        "not device"
+    }
+
+    fn kind(&self) -> &'static str {
+        "nodev"
+    }
+
+    fn report(&self) -> DeviceStatus {
+        DeviceStatus::new(&self.name(), DeviceState::Nodev)
+    }
+
+    fn update(&mut self) {}
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::NONE
+    }
+}
+
+impl fmt::Debug for dyn Device {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Device")
+            .field("id", &self.id())
+            .field("kind", &self.kind())
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::{Capabilities, Device};
+    use crate::id::DeviceId;
+    use crate::status::{DeviceState, DeviceStatus};
+    use crate::SmartDevice;
+
+    /// Device from other crate
+    struct Lamp {
+        id: DeviceId,
+        on: bool,
+    }
+
+    impl Clone for Lamp {
+        fn clone(&self) -> Self {
+            Lamp {
+                id: DeviceId::fresh(),
+                on: self.on,
+            }
+        }
+    }
+
+    impl Device for Lamp {
+        fn id(&self) -> DeviceId {
+            self.id
+        }
+
+        fn name_str(&self) -> &str {
+            "lamp"
+        }
+
+        fn kind(&self) -> &'static str {
+            "lamp"
+        }
+
+        fn report(&self) -> DeviceStatus {
+            let text: String = format!("lamp is {}", if self.on { "on" } else { "off" });
+            DeviceStatus::new(&self.name(), DeviceState::Other(text))
+        }
+
+        fn update(&mut self) {}
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::SWITCH
+        }
+
+        fn switch(&mut self, enable: bool) -> bool {
+            self.on = enable;
+            true
+        }
+    }
+
+    #[test]
+    fn device_tests() {
+        let caps: Capabilities = Capabilities::SWITCH | Capabilities::POWER;
+        assert!(caps.contains(Capabilities::POWER));
+        assert!(!caps.contains(Capabilities::SWITCH | Capabilities::TEMPERATURE));
+        assert!(caps.contains(Capabilities::NONE));
+
+        let mut dev: SmartDevice = SmartDevice::new(Lamp {
+            id: DeviceId::fresh(),
+            on: false,
+        });
+        assert!(dev.switch(true));
+        let copy: SmartDevice = dev.clone();
+        dev.switch(false);
+        assert_ne!(dev.id(), copy.id());
+        assert_eq!("name: lamp\nlamp is on", copy.device().status());
+        assert!(!dev.downcast_ref::<Lamp>().unwrap().on);
+        assert!(dev.downcast_ref::<crate::SmartSocket>().is_none());
+    }
+
+    #[test]
+    fn builtin_device_tests() {
+        let socket: SmartDevice = SmartDevice::from(crate::SmartSocket::new("socket"));
+        let thermometer: SmartDevice =
+            SmartDevice::from(crate::SmartThermometer::new("thermometer"));
+        assert_eq!("socket", socket.kind());
+        assert!(socket.capabilities().contains(Capabilities::SWITCH));
+        assert_eq!("thermometer", thermometer.kind());
+        assert!(thermometer
+            .capabilities()
+            .contains(Capabilities::TEMPERATURE));
+
+        /* empty device slot */
+        let nodev: SmartDevice = SmartDevice::default();
+        assert!(nodev.is_nodev());
+        assert_eq!("nodev", nodev.kind());
+        assert_eq!(Capabilities::NONE, nodev.capabilities());
+    }
+}
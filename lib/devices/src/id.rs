@@ -0,0 +1,122 @@
+//! Device identifiers
+//! every device takes unique id, when it is created,
+//! ids are never reused while the program runs.
+//! Id is not name: devices with the same name have different ids.
+//! Stored device keeps its id, when it is loaded, so ids stay
+//! unique only while every stored device is loaded once.
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Next free id, 0 is `DeviceId::NONE`,
+/// `u64::MAX` is never given, it marks exhausted ids
+static NEXT: AtomicU64 = AtomicU64::new(1);
+
+/// Unique device id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u64", into = "u64")
+)]
+pub struct DeviceId(u64);
+
+impl DeviceId {
+    /// Id of not a device
+    pub const NONE: Self = DeviceId(0);
+
+    /// Take new unique id, panics, if all ids are taken
+    pub fn fresh() -> Self {
+        Self::try_fresh().expect("device ids are exhausted")
+    }
+
+    /// Take new unique id
+    ///
+    /// -`return`   - device id, or None, if all ids are taken
+    pub fn try_fresh() -> Option<Self> {
+        let mut next: u64 = NEXT.load(Ordering::Relaxed);
+        loop {
+            if u64::MAX == next {
+                return None;
+            }
+            match NEXT.compare_exchange_weak(next, next + 1, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return Some(DeviceId(next)),
+                Err(actual) => next = actual,
+            }
+        }
+    }
+
+    /// Id of the stored device, new ids will not be the same,
+    /// loading the same device twice gives two devices with one id
+    /// -`raw`      - stored id
+    ///
+    /// -`return`   - device id, or None, if raw is `DeviceId::NONE`
+    ///               or leaves no new id after it
+    pub fn from_raw(raw: u64) -> Option<Self> {
+        if 0 == raw || raw >= u64::MAX - 1 {
+            return None;
+        }
+        NEXT.fetch_max(raw + 1, Ordering::Relaxed);
+        Some(DeviceId(raw))
+    }
+
+    /// Number of the id
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl TryFrom<u64> for DeviceId {
+    type Error = &'static str;
+
+    /// Id of the stored device
+    fn try_from(raw: u64) -> Result<Self, Self::Error> {
+        Self::from_raw(raw).ok_or("device id is out of range")
+    }
+}
+
+impl From<DeviceId> for u64 {
+    fn from(id: DeviceId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "#{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::id::DeviceId;
+    use crate::{SmartDevice, SmartDeviceAccess, SmartSocket};
+
+    #[test]
+    fn device_id_tests() {
+        let first: DeviceId = DeviceId::fresh();
+        let second: DeviceId = DeviceId::fresh();
+        assert!(first < second);
+        assert_ne!(DeviceId::NONE, first);
+
+        /* stored id is not given again */
+        let stored: DeviceId = DeviceId::from_raw(second.raw() + 100).unwrap();
+        assert!(stored < DeviceId::fresh());
+        assert_eq!(format!("#{}", stored.raw()), stored.to_string());
+        /* the last ids would leave no new id, 0 is not a device */
+        assert_eq!(None, DeviceId::from_raw(u64::MAX));
+        assert_eq!(None, DeviceId::from_raw(u64::MAX - 1));
+        assert_eq!(None, DeviceId::from_raw(0));
+        assert!(DeviceId::try_from(u64::MAX).is_err());
+        assert!(DeviceId::fresh() > stored);
+    }
+
+    #[test]
+    fn device_clone_id_tests() {
+        /* copy is other device with the same name */
+        let socket: SmartDevice = SmartDevice::from(SmartSocket::new("socket"));
+        let copy: SmartDevice = socket.clone();
+        assert_eq!(socket.name(), copy.name());
+        assert_ne!(socket.id(), copy.id());
+        assert_eq!(DeviceId::NONE, SmartDevice::nodev().id());
+    }
+}
@@ -1,17 +1,135 @@
 //! Container for devices
+//! device types implement `device::Device`,
+//! `SmartDevice` owns any of them, every device has unique `id::DeviceId`
+//! devices report `status::DeviceStatus`, text status is its `Display`
+//! output text is taken from `locale` catalogs
+//! readings are kept in `units` types
+//! hardware is accessed through `backend::DeviceBackend`
+//! sockets are switched over network with `tcp`,
+//! thermometers push readings with `udp`
+//! devices are paired over `radio` and tracked by `registry::DeviceRegistry`
+//! with `serde` feature built-in devices are serialized
+pub mod backend;
+pub mod device;
+pub mod id;
+pub mod locale;
+pub mod proto;
+pub mod radio;
+pub mod registry;
+#[cfg(feature = "serde")]
+mod serde_impl;
 pub mod smartsocket;
 pub mod smartthermometer;
+pub mod status;
+pub mod tcp;
+pub mod udp;
+pub mod units;
+pub use crate::device::{Capabilities, Device, Nodev};
+pub use crate::id::DeviceId;
 use crate::smartsocket::SmartSocket;
 use crate::smartthermometer::SmartThermometer;
+pub use crate::status::{DeviceState, DeviceStatus};
+pub use crate::units::{Energy, Power, TempUnit, Temperature};
+use std::any::Any;
+use std::fmt;
 
-/// Smarthome device, for a while can be:
-/// 0. `Nodev`
-/// 1. `Socket`
-/// 2. `Thermometer`
-pub enum SmartDevice {
-    Nodev,
-    Socket(SmartSocket),
-    Thermometer(SmartThermometer),
+/// Smarthome device, owns any device type,
+/// not a device is `Nodev`
+///
+/// -`dev`  - boxed device
+pub struct SmartDevice {
+    dev: Box<dyn Device>,
+}
+
+impl SmartDevice {
+    /// Device ctor
+    ///
+    /// -`dev`      - device to own
+    ///
+    /// -`return`   - new smart device
+    pub fn new<D: Device>(dev: D) -> Self {
+        SmartDevice { dev: Box::new(dev) }
+    }
+
+    /// Not a device ctor
+    /// -`return`   - placeholder device
+    pub fn nodev() -> Self {
+        Self::new(Nodev)
+    }
+
+    /// Return true, if it is not a device
+    pub fn is_nodev(&self) -> bool {
+        self.downcast_ref::<Nodev>().is_some()
+    }
+
+    /// Get device interface
+    pub fn device(&self) -> &dyn Device {
+        &*self.dev
+    }
+
+    /// Get mutable device interface
+    pub fn device_mut(&mut self) -> &mut dyn Device {
+        &mut *self.dev
+    }
+
+    /// Take boxed device out
+    pub fn into_inner(self) -> Box<dyn Device> {
+        self.dev
+    }
+
+    /// Get device id
+    pub fn id(&self) -> DeviceId {
+        self.dev.id()
+    }
+
+    /// Get device name without copying it
+    pub fn name_str(&self) -> &str {
+        self.dev.name_str()
+    }
+
+    /// Get device kind
+    /// -`return` short type name, like "socket"
+    pub fn kind(&self) -> &'static str {
+        self.dev.kind()
+    }
+
+    /// Get device capabilities
+    pub fn capabilities(&self) -> Capabilities {
+        self.dev.capabilities()
+    }
+
+    /// Get structured device status
+    /// -`return` device status, taken now
+    pub fn report(&self) -> DeviceStatus {
+        self.dev.report()
+    }
+
+    /// Update device status
+    pub fn update(&mut self) {
+        self.dev.update()
+    }
+
+    /// Switch device on or off
+    /// -`enable`   - new state
+    ///
+    /// -`return`   - true, if device can be switched
+    pub fn switch(&mut self, enable: bool) -> bool {
+        self.dev.switch(enable)
+    }
+
+    /// Access concrete device type
+    /// -`return` device reference, or None, if device is of other type
+    pub fn downcast_ref<D: Device>(&self) -> Option<&D> {
+        let dev: &dyn Any = &*self.dev;
+        dev.downcast_ref()
+    }
+
+    /// Mutably access concrete device type
+    /// -`return` device reference, or None, if device is of other type
+    pub fn downcast_mut<D: Device>(&mut self) -> Option<&mut D> {
+        let dev: &mut dyn Any = &mut *self.dev;
+        dev.downcast_mut()
+    }
 }
 
 /// Contain methods to easy access
@@ -29,84 +147,58 @@ impl SmartDeviceAccess for SmartDevice {
     /// Get device name
     /// -`return` device name
     fn name(&self) -> String {
-        match self {
-            SmartDevice::Socket(the_socket) => the_socket.text.clone(),
-            SmartDevice::Thermometer(the_thermometer) => the_thermometer.text.clone(),
-            SmartDevice::Nodev => "not device".to_string(),
-        }
+        self.dev.name()
     }
 
     /// Get device status
     /// -`return' device status
     fn status(&self) -> String {
-        match self {
-            SmartDevice::Socket(the_socket) => {
-                /* todo: here should be lang translation table access */
-                let name_str: &'static str = "name: ";
-                let status_str: &'static str = "\nstatus: ";
-                let on_str: &'static str = "on";
-                let off_str: &'static str = "off";
-                let power_str: &'static str = "\npower: ";
-                #[allow(non_snake_case)]
-                //to avoid rust erratic warning about milliwatt abbreviation
-                let mW_str: &'static str = " mW ";
-                #[warn(non_snake_case)] //to avoid rust erratic warning about milliwatt abbreviation
-                let curr_status_str: &'static str =
-                    if the_socket.enabled { on_str } else { off_str };
-
-                /* construct status */
-                let mut dev_status: String = name_str.to_string();
-                dev_status.push_str(&the_socket.text);
-                dev_status.push_str(status_str);
-                dev_status.push_str(curr_status_str);
-                if the_socket.enabled {
-                    dev_status.push_str(power_str);
-                    dev_status.push_str(&the_socket.power_mW.to_string());
-                    dev_status.push_str(mW_str);
-                };
-
-                dev_status
-            }
-
-            SmartDevice::Thermometer(the_thermometer) => {
-                /* todo: here should be lang translation table access */
-                let name_str: &'static str = "name: ";
-                let temp_str: &'static str = "\ntemp: ";
-
-                /* construct status */
-                let mut dev_status: String = name_str.to_string();
-                dev_status.push_str(&the_thermometer.text);
-                dev_status.push_str(temp_str);
-                dev_status.push_str(&the_thermometer.temp.to_string());
-
-                dev_status
-            }
-
-            SmartDevice::Nodev => "not device".to_string(),
-        }
+        self.dev.status()
+    }
+}
+
+impl From<Box<dyn Device>> for SmartDevice {
+    fn from(dev: Box<dyn Device>) -> Self {
+        SmartDevice { dev }
+    }
+}
+
+impl From<SmartSocket> for SmartDevice {
+    fn from(socket: SmartSocket) -> Self {
+        Self::new(socket)
+    }
+}
+
+impl From<SmartThermometer> for SmartDevice {
+    fn from(thermometer: SmartThermometer) -> Self {
+        Self::new(thermometer)
     }
 }
 
 impl Default for SmartDevice {
     /// Defauld smart device is not a device
     fn default() -> Self {
-        SmartDevice::Nodev
+        Self::nodev()
     }
 }
 
-/* clone for smart device have special rules */
+/* clone for smart device have special rules,
+ * every device type copies itself, copy takes new id
+ */
 impl Clone for SmartDevice {
     fn clone(&self) -> Self {
-        match self {
-            SmartDevice::Socket(the_socket) => SmartDevice::Socket(the_socket.clone()),
-            SmartDevice::Thermometer(the_thermometer) => {
-                SmartDevice::Thermometer(the_thermometer.clone())
-            }
-            SmartDevice::Nodev => SmartDevice::Nodev,
+        SmartDevice {
+            dev: self.dev.clone_box(),
         }
     }
 }
 
+impl fmt::Debug for SmartDevice {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.dev, fmt)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::SmartDevice;
@@ -118,8 +210,8 @@ mod tests {
         let mut smartsocket0: SmartSocket = SmartSocket::new("test socket0");
         smartsocket0.en();
         let smartthermometer0: SmartThermometer = SmartThermometer::new("test thermometer");
-        let device0: SmartDevice = SmartDevice::Socket(smartsocket0);
-        let device1: SmartDevice = SmartDevice::Thermometer(smartthermometer0);
+        let device0: SmartDevice = SmartDevice::from(smartsocket0);
+        let device1: SmartDevice = SmartDevice::from(smartthermometer0);
 
         let name0: String = device0.name();
         let stat0: String = device0.status();
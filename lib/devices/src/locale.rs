@@ -0,0 +1,307 @@
+//! Localization of device and house text
+//! every output label has a message key,
+//! catalog maps keys to text of one locale.
+//! Catalogs are `key = text` files, English and Russian
+//! are built in, other locales are loaded at runtime.
+//! Current catalog is process wide, English by default.
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Message key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MsgKey {
+    NotDevice,
+    Name,
+    Status,
+    On,
+    Off,
+    Power,
+    Temp,
+    Milliwatt,
+    Energy,
+    WattHour,
+    House,
+    Room,
+    LabelName,
+    LabelState,
+    LabelOn,
+    LabelOff,
+    LabelPower,
+    LabelTemp,
+    LabelHouse,
+    LabelRoom,
+}
+
+impl MsgKey {
+    /// All keys
+    pub const ALL: [MsgKey; 20] = [
+        MsgKey::NotDevice,
+        MsgKey::Name,
+        MsgKey::Status,
+        MsgKey::On,
+        MsgKey::Off,
+        MsgKey::Power,
+        MsgKey::Temp,
+        MsgKey::Milliwatt,
+        MsgKey::Energy,
+        MsgKey::WattHour,
+        MsgKey::House,
+        MsgKey::Room,
+        MsgKey::LabelName,
+        MsgKey::LabelState,
+        MsgKey::LabelOn,
+        MsgKey::LabelOff,
+        MsgKey::LabelPower,
+        MsgKey::LabelTemp,
+        MsgKey::LabelHouse,
+        MsgKey::LabelRoom,
+    ];
+
+    /// Key in catalog file
+    pub fn id(self) -> &'static str {
+        match self {
+            MsgKey::NotDevice => "device.nodev",
+            MsgKey::Name => "device.name",
+            MsgKey::Status => "device.status",
+            MsgKey::On => "device.on",
+            MsgKey::Off => "device.off",
+            MsgKey::Power => "device.power",
+            MsgKey::Temp => "device.temp",
+            MsgKey::Milliwatt => "unit.milliwatt",
+            MsgKey::Energy => "device.energy",
+            MsgKey::WattHour => "unit.watt_hour",
+            MsgKey::House => "house.name",
+            MsgKey::Room => "room.name",
+            MsgKey::LabelName => "label.name",
+            MsgKey::LabelState => "label.state",
+            MsgKey::LabelOn => "label.on",
+            MsgKey::LabelOff => "label.off",
+            MsgKey::LabelPower => "label.power",
+            MsgKey::LabelTemp => "label.temp",
+            MsgKey::LabelHouse => "label.house",
+            MsgKey::LabelRoom => "label.room",
+        }
+    }
+
+    /// Key by its catalog file id
+    /// -`return` key, or None, if id is unknown
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key: &MsgKey| id == key.id())
+    }
+}
+
+/// Catalog load error
+///
+/// -`Io`       - catalog file can't be read
+/// -`Syntax`   - line is not `key = text`
+/// -`Unknown`  - locale is not built in
+#[derive(Debug)]
+pub enum LocaleError {
+    Io(io::Error),
+    Syntax { line: usize },
+    Unknown(String),
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocaleError::Io(err) => write!(fmt, "catalog can't be read: {}", err),
+            LocaleError::Syntax { line } => {
+                write!(fmt, "catalog line {} is not `key = text`", line)
+            }
+            LocaleError::Unknown(locale) => write!(fmt, "locale {} is not built in", locale),
+        }
+    }
+}
+
+impl std::error::Error for LocaleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LocaleError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LocaleError {
+    fn from(err: io::Error) -> Self {
+        LocaleError::Io(err)
+    }
+}
+
+/// Built-in English catalog source
+const EN: &str = include_str!("../locales/en.lang");
+/// Built-in Russian catalog source
+const RU: &str = include_str!("../locales/ru.lang");
+
+/// Messages of one locale, missing messages are taken from English
+///
+/// -`locale`   - locale name, like "en"
+/// -`messages` - text by key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<MsgKey, String>,
+}
+
+impl Catalog {
+    /// Parse catalog text, unknown keys are skipped
+    ///
+    /// -`locale`   - locale name
+    /// -`text`     - `key = text` lines, `#` starts a comment line
+    ///
+    /// -`return`   - new catalog, or syntax error
+    pub fn parse(locale: &str, text: &str) -> Result<Self, LocaleError> {
+        let mut messages: HashMap<MsgKey, String> = HashMap::new();
+        for (at, line) in text.lines().enumerate() {
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, msg) = line
+                .split_once('=')
+                .ok_or(LocaleError::Syntax { line: at + 1 })?;
+            if let Some(key) = MsgKey::from_id(id.trim()) {
+                messages.insert(key, msg.trim().to_string());
+            }
+        }
+        Ok(Catalog {
+            locale: locale.to_string(),
+            messages,
+        })
+    }
+
+    /// Load catalog file, locale is named by the file stem
+    ///
+    /// -`path`     - catalog file, like "locales/ru.lang"
+    ///
+    /// -`return`   - new catalog, or load error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LocaleError> {
+        let path: &Path = path.as_ref();
+        let text: String = fs::read_to_string(path)?;
+        let locale: &str = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        Self::parse(locale, &text)
+    }
+
+    /// Built-in catalog
+    ///
+    /// -`locale`   - "en" or "ru"
+    ///
+    /// -`return`   - catalog, or error, if locale is not built in
+    pub fn builtin(locale: &str) -> Result<Self, LocaleError> {
+        match locale {
+            "en" => Self::parse(locale, EN),
+            "ru" => Self::parse(locale, RU),
+            _ => Err(LocaleError::Unknown(locale.to_string())),
+        }
+    }
+
+    /// Locale name
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Message text
+    /// -`key`      - message key
+    ///
+    /// -`return`   - text of the locale, English text if it is missing
+    pub fn get(&self, key: MsgKey) -> &str {
+        match self.messages.get(&key) {
+            Some(msg) => msg,
+            None => english()
+                .messages
+                .get(&key)
+                .map_or(key.id(), String::as_str),
+        }
+    }
+}
+
+/// Built-in English catalog
+fn english() -> &'static Arc<Catalog> {
+    static ENGLISH: OnceLock<Arc<Catalog>> = OnceLock::new();
+    ENGLISH.get_or_init(|| Arc::new(Catalog::builtin("en").expect("built-in catalog")))
+}
+
+/// Current catalog, None is English
+static CURRENT: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+/// Get current catalog
+pub fn current() -> Arc<Catalog> {
+    let current = CURRENT.read().unwrap_or_else(|err| err.into_inner());
+    match current.as_ref() {
+        Some(catalog) => catalog.clone(),
+        None => english().clone(),
+    }
+}
+
+/// Make catalog current for the whole process
+/// -`catalog`  - new current catalog
+pub fn set_locale(catalog: Catalog) {
+    let mut current = CURRENT.write().unwrap_or_else(|err| err.into_inner());
+    *current = Some(Arc::new(catalog));
+}
+
+/// Make built-in catalog current
+/// -`locale`   - "en" or "ru"
+///
+/// -`return`   - error, if locale is not built in
+pub fn select_locale(locale: &str) -> Result<(), LocaleError> {
+    set_locale(Catalog::builtin(locale)?);
+    Ok(())
+}
+
+/// Text of current locale
+/// -`key`      - message key
+///
+/// -`return`   - message text
+pub fn tr(key: MsgKey) -> String {
+    current().get(key).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::{Catalog, LocaleError, MsgKey};
+
+    #[test]
+    fn catalog_tests() {
+        /* built-in catalogs have every message */
+        for locale in ["en", "ru"] {
+            let catalog: Catalog = Catalog::builtin(locale).unwrap();
+            for key in MsgKey::ALL {
+                assert!(
+                    catalog.messages.contains_key(&key),
+                    "{} {}",
+                    locale,
+                    key.id()
+                );
+            }
+        }
+        let ru: Catalog = Catalog::builtin("ru").unwrap();
+        assert_eq!("вкл", ru.get(MsgKey::On));
+        assert_eq!("Вкл", ru.get(MsgKey::LabelOn));
+        /* English house and room reports have no prefix */
+        let en: Catalog = Catalog::builtin("en").unwrap();
+        assert_eq!("", en.get(MsgKey::LabelRoom));
+        assert_eq!("комната", ru.get(MsgKey::LabelRoom));
+        assert!(matches!(
+            Catalog::builtin("de"),
+            Err(LocaleError::Unknown(_))
+        ));
+
+        /* missing message falls back to English */
+        let de: Catalog = Catalog::parse("de", "# Deutsch\n\ndevice.on = an\nother = x").unwrap();
+        assert_eq!("an", de.get(MsgKey::On));
+        assert_eq!("off", de.get(MsgKey::Off));
+        assert!(matches!(
+            Catalog::parse("de", "device.on = an\ndevice.off"),
+            Err(LocaleError::Syntax { line: 2 })
+        ));
+    }
+}
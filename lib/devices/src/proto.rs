@@ -0,0 +1,369 @@
+//! Smart socket control protocol
+//! every message is one frame:
+//! `version: u8, code: u8, len: u16, payload: [u8; len]`,
+//! numbers are big endian. Client sends a request frame
+//! and reads one response frame.
+//!
+//! requests have no payload:
+//! - `0x01` enable, `0x02` disable, `0x03` status, `0x04` power
+//!
+//! responses:
+//! - `0x80` done, no payload
+//! - `0x81` status, `enabled: u8, power_mw: u64, energy_uj: u64`
+//! - `0x82` power, `power_mw: u64`
+//! - `0xc0` error, `code: u8`, then UTF-8 text
+use crate::units::{Energy, Power};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Protocol version
+pub const VERSION: u8 = 1;
+
+/// Max frame payload
+pub const MAX_PAYLOAD: usize = 1024;
+
+/// Control request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Enable,
+    Disable,
+    Status,
+    Power,
+}
+
+/// Error response reason
+///
+/// -`Version`  - frame version is not supported
+/// -`Request`  - request is not known
+/// -`Device`   - device can't do the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Version = 1,
+    Request = 2,
+    Device = 3,
+}
+
+/// Control response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Done,
+    Status {
+        enabled: bool,
+        power: Power,
+        energy: Energy,
+    },
+    Power(Power),
+    Error {
+        code: ErrorCode,
+        text: String,
+    },
+}
+
+/// Frame error
+///
+/// -`Io`       - stream error
+/// -`Version`  - frame version is not supported, frame is skipped
+/// -`Code`     - frame code is not known, frame is skipped
+/// -`Payload`  - payload does not match the code
+/// -`Length`   - payload is longer than `MAX_PAYLOAD`
+#[derive(Debug)]
+pub enum ProtoError {
+    Io(io::Error),
+    Version(u8),
+    Code(u8),
+    Payload,
+    Length(usize),
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtoError::Io(err) => write!(fmt, "stream error: {}", err),
+            ProtoError::Version(version) => {
+                write!(fmt, "protocol version {} is not supported", version)
+            }
+            ProtoError::Code(code) => write!(fmt, "frame code {:#04x} is not known", code),
+            ProtoError::Payload => fmt.write_str("frame payload is malformed"),
+            ProtoError::Length(len) => write!(fmt, "frame payload of {} bytes is too long", len),
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProtoError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProtoError {
+    fn from(err: io::Error) -> Self {
+        ProtoError::Io(err)
+    }
+}
+
+/// Write one frame
+/// -`code`     - frame code
+/// -`payload`  - frame payload, not longer than `MAX_PAYLOAD`
+pub fn write_frame<W: Write>(out: &mut W, code: u8, payload: &[u8]) -> Result<(), ProtoError> {
+    if payload.len() > MAX_PAYLOAD {
+        return Err(ProtoError::Length(payload.len()));
+    }
+    let mut frame: Vec<u8> = Vec::with_capacity(4 + payload.len());
+    frame.push(VERSION);
+    frame.push(code);
+    frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    frame.extend_from_slice(payload);
+    /* one write, so the frame is not split into small packets */
+    out.write_all(&frame)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Read one frame, whole frame is consumed even if it is rejected
+/// -`return`   - frame code and payload
+pub fn read_frame<R: Read>(input: &mut R) -> Result<(u8, Vec<u8>), ProtoError> {
+    let mut header: [u8; 4] = [0; 4];
+    input.read_exact(&mut header)?;
+    let len: usize = u16::from_be_bytes([header[2], header[3]]) as usize;
+    if len > MAX_PAYLOAD {
+        /* too long payload is skipped, not buffered */
+        let skipped: u64 = io::copy(&mut input.take(len as u64), &mut io::sink())?;
+        if skipped < len as u64 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        return Err(ProtoError::Length(len));
+    }
+    let mut payload: Vec<u8> = vec![0; len];
+    input.read_exact(&mut payload)?;
+    if VERSION != header[0] {
+        return Err(ProtoError::Version(header[0]));
+    }
+    Ok((header[1], payload))
+}
+
+/// Take big endian u64 from payload
+fn take_u64(payload: &[u8]) -> Result<(u64, &[u8]), ProtoError> {
+    if payload.len() < 8 {
+        return Err(ProtoError::Payload);
+    }
+    let (num, rest) = payload.split_at(8);
+    let mut bytes: [u8; 8] = [0; 8];
+    bytes.copy_from_slice(num);
+    Ok((u64::from_be_bytes(bytes), rest))
+}
+
+impl Request {
+    /// Frame code
+    pub fn code(self) -> u8 {
+        match self {
+            Request::Enable => 0x01,
+            Request::Disable => 0x02,
+            Request::Status => 0x03,
+            Request::Power => 0x04,
+        }
+    }
+
+    /// Write request frame
+    pub fn write<W: Write>(self, out: &mut W) -> Result<(), ProtoError> {
+        write_frame(out, self.code(), &[])
+    }
+
+    /// Read request frame
+    pub fn read<R: Read>(input: &mut R) -> Result<Self, ProtoError> {
+        let (code, payload) = read_frame(input)?;
+        let request: Request = match code {
+            0x01 => Request::Enable,
+            0x02 => Request::Disable,
+            0x03 => Request::Status,
+            0x04 => Request::Power,
+            _ => return Err(ProtoError::Code(code)),
+        };
+        if !payload.is_empty() {
+            return Err(ProtoError::Payload);
+        }
+        Ok(request)
+    }
+}
+
+impl Response {
+    /// Write response frame, long error text is cut
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), ProtoError> {
+        let mut payload: Vec<u8> = Vec::new();
+        let code: u8 = match self {
+            Response::Done => 0x80,
+            Response::Status {
+                enabled,
+                power,
+                energy,
+            } => {
+                payload.push(*enabled as u8);
+                payload.extend_from_slice(&power.milliwatts().to_be_bytes());
+                payload.extend_from_slice(&energy.microjoules().to_be_bytes());
+                0x81
+            }
+            Response::Power(power) => {
+                payload.extend_from_slice(&power.milliwatts().to_be_bytes());
+                0x82
+            }
+            Response::Error { code, text } => {
+                payload.push(*code as u8);
+                let mut end: usize = text.len().min(MAX_PAYLOAD - 1);
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                payload.extend_from_slice(&text.as_bytes()[..end]);
+                0xc0
+            }
+        };
+        write_frame(out, code, &payload)
+    }
+
+    /// Read response frame
+    pub fn read<R: Read>(input: &mut R) -> Result<Self, ProtoError> {
+        let (code, payload) = read_frame(input)?;
+        let response: Response = match code {
+            0x80 if payload.is_empty() => Response::Done,
+            0x81 => {
+                let (&enabled, rest) = payload.split_first().ok_or(ProtoError::Payload)?;
+                let (power, rest) = take_u64(rest)?;
+                let (energy, rest) = take_u64(rest)?;
+                if enabled > 1 || !rest.is_empty() {
+                    return Err(ProtoError::Payload);
+                }
+                Response::Status {
+                    enabled: 1 == enabled,
+                    power: Power::from_milliwatts(power),
+                    energy: Energy::from_microjoules(energy),
+                }
+            }
+            0x82 => match take_u64(&payload)? {
+                (power, []) => Response::Power(Power::from_milliwatts(power)),
+                _ => return Err(ProtoError::Payload),
+            },
+            0xc0 => {
+                let (&code, text) = payload.split_first().ok_or(ProtoError::Payload)?;
+                let code: ErrorCode = match code {
+                    1 => ErrorCode::Version,
+                    2 => ErrorCode::Request,
+                    3 => ErrorCode::Device,
+                    _ => return Err(ProtoError::Payload),
+                };
+                let text: String =
+                    String::from_utf8(text.to_vec()).map_err(|_| ProtoError::Payload)?;
+                Response::Error { code, text }
+            }
+            0x80 => return Err(ProtoError::Payload),
+            _ => return Err(ProtoError::Code(code)),
+        };
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::{self, ErrorCode, ProtoError, Request, Response};
+    use crate::units::{Energy, Power};
+    use std::io::Cursor;
+
+    #[test]
+    fn proto_tests() {
+        let mut wire: Vec<u8> = Vec::new();
+        let requests = [
+            Request::Enable,
+            Request::Disable,
+            Request::Status,
+            Request::Power,
+        ];
+        for request in requests {
+            request.write(&mut wire).unwrap();
+        }
+        assert_eq!(&[proto::VERSION, 0x01, 0, 0], &wire[..4]);
+        let responses = [
+            Response::Done,
+            Response::Status {
+                enabled: true,
+                power: Power::from_milliwatts(10000),
+                energy: Energy::from_microjoules(36),
+            },
+            Response::Power(Power::from_milliwatts(u64::MAX)),
+            Response::Error {
+                code: ErrorCode::Device,
+                text: "реле".repeat(300),
+            },
+        ];
+        for response in responses.iter() {
+            response.write(&mut wire).unwrap();
+        }
+
+        let mut input: Cursor<Vec<u8>> = Cursor::new(wire);
+        for request in requests {
+            assert_eq!(request, Request::read(&mut input).unwrap());
+        }
+        for response in responses[..3].iter() {
+            assert_eq!(*response, Response::read(&mut input).unwrap());
+        }
+        /* long text is cut on char boundary */
+        match Response::read(&mut input).unwrap() {
+            Response::Error { code, text } => {
+                assert_eq!(ErrorCode::Device, code);
+                assert!(
+                    text.len() < proto::MAX_PAYLOAD && text.chars().all(|c| "реле".contains(c))
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(Request::read(&mut input), Err(ProtoError::Io(_))));
+    }
+
+    #[test]
+    fn proto_reject_tests() {
+        /* rejected frames are consumed, next frame is read */
+        let wire: Vec<u8> = vec![
+            2, 0x01, 0, 1, 0xff, // other version
+            1, 0x7f, 0, 0, // unknown code
+            1, 0x03, 0, 1, 0, // payload of no payload request
+            1, 0x04, 0, 0,
+        ];
+        let mut input: Cursor<Vec<u8>> = Cursor::new(wire);
+        assert!(matches!(
+            Request::read(&mut input),
+            Err(ProtoError::Version(2))
+        ));
+        assert!(matches!(
+            Request::read(&mut input),
+            Err(ProtoError::Code(0x7f))
+        ));
+        assert!(matches!(
+            Request::read(&mut input),
+            Err(ProtoError::Payload)
+        ));
+        assert_eq!(Request::Power, Request::read(&mut input).unwrap());
+
+        let mut input: Cursor<Vec<u8>> = Cursor::new(vec![1, 0x81, 0, 2, 1, 0]);
+        assert!(matches!(
+            Response::read(&mut input),
+            Err(ProtoError::Payload)
+        ));
+        let payload: Vec<u8> = vec![0; proto::MAX_PAYLOAD + 1];
+        assert!(matches!(
+            proto::write_frame(&mut Vec::new(), 0x80, &payload),
+            Err(ProtoError::Length(_))
+        ));
+
+        /* too long frame is rejected, but consumed */
+        let mut wire: Vec<u8> = vec![1, 0x04, 0xff, 0xff];
+        wire.resize(4 + 0xffff, 0);
+        wire.extend_from_slice(&[1, 0x04, 0, 0]);
+        let mut input: Cursor<Vec<u8>> = Cursor::new(wire);
+        assert!(matches!(
+            Request::read(&mut input),
+            Err(ProtoError::Length(0xffff))
+        ));
+        assert_eq!(Request::Power, Request::read(&mut input).unwrap());
+        let mut input: Cursor<Vec<u8>> = Cursor::new(vec![1, 0x04, 0xff, 0xff, 0]);
+        assert!(matches!(Request::read(&mut input), Err(ProtoError::Io(_))));
+    }
+}
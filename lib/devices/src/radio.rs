@@ -0,0 +1,204 @@
+//! Radio network of smarthome devices
+//! not paired devices advertise themselves,
+//! pairing gives hardware access to the device,
+//! unpaired device does not answer anymore.
+//! `SimRadio` is a simulated network for tests.
+use crate::backend::{BackendError, SharedBackend, SimBackend};
+use crate::registry::PairState;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Radio hardware address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RadioAddr(pub u64);
+
+impl fmt::Display for RadioAddr {
+    /// Address is printed as six hex octets
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let octets: [u8; 8] = self.0.to_be_bytes();
+        write!(
+            fmt,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            octets[2], octets[3], octets[4], octets[5], octets[6], octets[7]
+        )
+    }
+}
+
+/// Device type, as it is advertised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RadioKind {
+    Socket,
+    Thermometer,
+}
+
+/// Advertisement of not paired device
+///
+/// -`addr`     - device address
+/// -`kind`     - device type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advert {
+    pub addr: RadioAddr,
+    pub kind: RadioKind,
+}
+
+/// Lifecycle error
+///
+/// -`NotFound`     - device is not in range
+/// -`Refused`      - device is paired with other controller
+/// -`State`        - lifecycle step is not allowed in the device state
+/// -`Backend`      - radio failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RadioError {
+    NotFound(RadioAddr),
+    Refused(RadioAddr),
+    State(RadioAddr, PairState),
+    Backend(BackendError),
+}
+
+impl fmt::Display for RadioError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RadioError::NotFound(addr) => write!(fmt, "device {} is not found", addr),
+            RadioError::Refused(addr) => write!(fmt, "device {} refused pairing", addr),
+            RadioError::State(addr, state) => write!(fmt, "device {} is {:?}", addr, state),
+            RadioError::Backend(err) => write!(fmt, "radio error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RadioError {}
+
+impl From<BackendError> for RadioError {
+    fn from(err: BackendError) -> Self {
+        RadioError::Backend(err)
+    }
+}
+
+/// Radio network access
+pub trait RadioNetwork: Send {
+    /// Find not paired devices in range
+    fn discover(&mut self) -> Result<Vec<Advert>, RadioError>;
+
+    /// Pair device
+    /// -`addr`     - device address
+    ///
+    /// -`return`   - device hardware access
+    fn pair(&mut self, addr: RadioAddr) -> Result<SharedBackend, RadioError>;
+
+    /// Inform device, that it is not used anymore
+    /// -`addr`     - device address
+    fn unpair(&mut self, addr: RadioAddr) -> Result<(), RadioError>;
+}
+
+/// Simulated device on the air
+///
+/// -`advert`   - device advertisement
+/// -`backend`  - device hardware
+/// -`paired`   - device is paired
+/// -`in_range` - device answers
+struct SimRadioDevice {
+    advert: Advert,
+    backend: Arc<Mutex<SimBackend>>,
+    paired: bool,
+    in_range: bool,
+}
+
+/// Simulated radio network
+#[derive(Default)]
+pub struct SimRadio {
+    devices: Vec<SimRadioDevice>,
+}
+
+impl SimRadio {
+    /// Empty network ctor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add device into network
+    /// -`addr`     - device address
+    /// -`kind`     - device type
+    /// -`sim`      - device hardware
+    pub fn with_device(mut self, addr: RadioAddr, kind: RadioKind, mut sim: SimBackend) -> Self {
+        /* not paired device does not answer requests */
+        sim.set_offline(true);
+        self.devices.push(SimRadioDevice {
+            advert: Advert { addr, kind },
+            backend: Arc::new(Mutex::new(sim)),
+            paired: false,
+            in_range: true,
+        });
+        self
+    }
+
+    /// Move device in or out of range
+    pub fn set_in_range(&mut self, addr: RadioAddr, in_range: bool) {
+        if let Some(dev) = self.device(addr) {
+            dev.in_range = in_range;
+            dev.lock().set_offline(!in_range || !dev.paired);
+        }
+    }
+
+    /// Return true, if device is paired
+    pub fn is_paired(&self, addr: RadioAddr) -> bool {
+        self.devices
+            .iter()
+            .any(|dev: &SimRadioDevice| addr == dev.advert.addr && dev.paired)
+    }
+
+    /// Get device hardware, to act on it by hand
+    pub fn backend(&self, addr: RadioAddr) -> Option<Arc<Mutex<SimBackend>>> {
+        self.devices
+            .iter()
+            .find(|dev: &&SimRadioDevice| addr == dev.advert.addr)
+            .map(|dev: &SimRadioDevice| dev.backend.clone())
+    }
+
+    /// Get device by address
+    fn device(&mut self, addr: RadioAddr) -> Option<&mut SimRadioDevice> {
+        self.devices
+            .iter_mut()
+            .find(|dev: &&mut SimRadioDevice| addr == dev.advert.addr)
+    }
+}
+
+impl SimRadioDevice {
+    fn lock(&self) -> std::sync::MutexGuard<'_, SimBackend> {
+        self.backend.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl RadioNetwork for SimRadio {
+    fn discover(&mut self) -> Result<Vec<Advert>, RadioError> {
+        Ok(self
+            .devices
+            .iter()
+            .filter(|dev: &&SimRadioDevice| dev.in_range && !dev.paired)
+            .map(|dev: &SimRadioDevice| dev.advert)
+            .collect())
+    }
+
+    fn pair(&mut self, addr: RadioAddr) -> Result<SharedBackend, RadioError> {
+        let dev: &mut SimRadioDevice = match self.device(addr) {
+            Some(dev) if dev.in_range => dev,
+            _ => return Err(RadioError::NotFound(addr)),
+        };
+        if dev.paired {
+            return Err(RadioError::Refused(addr));
+        }
+        dev.paired = true;
+        dev.lock().set_offline(false);
+        Ok(dev.backend.clone())
+    }
+
+    fn unpair(&mut self, addr: RadioAddr) -> Result<(), RadioError> {
+        let dev: &mut SimRadioDevice = match self.device(addr) {
+            Some(dev) if dev.in_range => dev,
+            _ => return Err(RadioError::NotFound(addr)),
+        };
+        dev.paired = false;
+        /* unpaired device does not answer to the old controller */
+        dev.lock().set_offline(true);
+        Ok(())
+    }
+}
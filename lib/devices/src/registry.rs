@@ -0,0 +1,312 @@
+//! Device lifecycle
+//! device goes `Discovered` -> `Paired` -> `Commissioned` -> `Unpaired`:
+//! discovered device is paired over radio, paired device
+//! is commissioned as `SmartDevice`, and it is unpaired
+//! explicitly or when the commissioned device is dropped.
+//! `DeviceRegistry` tracks pairing state of every device address.
+use crate::backend::SharedBackend;
+use crate::id::DeviceId;
+use crate::radio::{Advert, RadioAddr, RadioError, RadioKind, RadioNetwork};
+use crate::smartsocket::SmartSocket;
+use crate::smartthermometer::SmartThermometer;
+use crate::SmartDevice;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// Device pairing state
+///
+/// -`Discovered`   - device is advertised, but not paired
+/// -`Paired`       - device is paired, but not used
+/// -`Commissioned` - device with the id uses the pairing
+/// -`Unpaired`     - device is not paired anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairState {
+    Discovered,
+    Paired,
+    Commissioned(DeviceId),
+    Unpaired,
+}
+
+/// Registry record of one address
+///
+/// -`kind`     - advertised device type
+/// -`state`    - pairing state
+/// -`backend`  - hardware access of the pairing
+struct Entry {
+    kind: RadioKind,
+    state: PairState,
+    backend: Option<SharedBackend>,
+}
+
+/// Registry data, shared by the registry handles and devices
+///
+/// -`radio`    - radio network
+/// -`entries`  - known addresses
+struct Registry {
+    radio: Box<dyn RadioNetwork>,
+    entries: HashMap<RadioAddr, Entry>,
+}
+
+/// Registry of paired devices, clones are handles of the same registry
+#[derive(Clone)]
+pub struct DeviceRegistry {
+    inner: Arc<Mutex<Registry>>,
+}
+
+impl DeviceRegistry {
+    /// Registry ctor
+    /// -`radio`    - radio network of devices
+    ///
+    /// -`return`   - empty registry
+    pub fn new<R: RadioNetwork + 'static>(radio: R) -> Self {
+        DeviceRegistry {
+            inner: Arc::new(Mutex::new(Registry {
+                radio: Box::new(radio),
+                entries: HashMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Find not paired devices, unpaired devices may be discovered again
+    /// -`return`   - advertisements of found devices
+    pub fn discover(&self) -> Result<Vec<Advert>, RadioError> {
+        let mut registry: MutexGuard<Registry> = self.lock();
+        let adverts: Vec<Advert> = registry.radio.discover()?;
+        for advert in adverts.iter() {
+            let entry: &mut Entry = registry.entries.entry(advert.addr).or_insert(Entry {
+                kind: advert.kind,
+                state: PairState::Unpaired,
+                backend: None,
+            });
+            if PairState::Unpaired == entry.state {
+                entry.kind = advert.kind;
+                entry.state = PairState::Discovered;
+            }
+        }
+        Ok(adverts)
+    }
+
+    /// Pair discovered device
+    /// -`addr`     - device address
+    pub fn pair(&self, addr: RadioAddr) -> Result<(), RadioError> {
+        let mut registry: MutexGuard<Registry> = self.lock();
+        match registry.entries.get(&addr).map(|entry: &Entry| entry.state) {
+            Some(PairState::Discovered) => {}
+            Some(state) => return Err(RadioError::State(addr, state)),
+            None => return Err(RadioError::NotFound(addr)),
+        }
+        let backend: SharedBackend = registry.radio.pair(addr)?;
+        if let Some(entry) = registry.entries.get_mut(&addr) {
+            entry.state = PairState::Paired;
+            entry.backend = Some(backend);
+        }
+        Ok(())
+    }
+
+    /// Make device of paired address, device is unpaired, when it is dropped
+    /// -`addr`     - device address
+    /// -`name`     - device name
+    ///
+    /// -`return`   - new device, controlled through the pairing
+    pub fn commission(&self, addr: RadioAddr, name: &str) -> Result<SmartDevice, RadioError> {
+        let mut registry: MutexGuard<Registry> = self.lock();
+        let entry: &mut Entry = registry
+            .entries
+            .get_mut(&addr)
+            .ok_or(RadioError::NotFound(addr))?;
+        let backend: SharedBackend = match (entry.state, &entry.backend) {
+            (PairState::Paired, Some(backend)) => backend.clone(),
+            (state, _) => return Err(RadioError::State(addr, state)),
+        };
+        let registration: Registration = Registration {
+            registry: Arc::downgrade(&self.inner),
+            addr,
+        };
+        let dev: SmartDevice = match entry.kind {
+            RadioKind::Socket => {
+                let mut socket: SmartSocket = SmartSocket::with_backend(name, backend);
+                socket.registration = Some(registration);
+                SmartDevice::from(socket)
+            }
+            RadioKind::Thermometer => {
+                let mut thermometer: SmartThermometer =
+                    SmartThermometer::with_backend(name, backend);
+                thermometer.registration = Some(registration);
+                SmartDevice::from(thermometer)
+            }
+        };
+        entry.state = PairState::Commissioned(dev.id());
+        Ok(dev)
+    }
+
+    /// Unpair paired or commissioned device,
+    /// commissioned device is kept, but it does not answer
+    /// -`addr`     - device address
+    pub fn unpair(&self, addr: RadioAddr) -> Result<(), RadioError> {
+        let mut registry: MutexGuard<Registry> = self.lock();
+        match registry.entries.get(&addr).map(|entry: &Entry| entry.state) {
+            Some(PairState::Paired) | Some(PairState::Commissioned(_)) => {}
+            Some(state) => return Err(RadioError::State(addr, state)),
+            None => return Err(RadioError::NotFound(addr)),
+        }
+        registry.radio.unpair(addr)?;
+        registry.forget(addr);
+        Ok(())
+    }
+
+    /// Get pairing state
+    /// -`addr`     - device address
+    ///
+    /// -`return`   - state, or None, if address is not known
+    pub fn state(&self, addr: RadioAddr) -> Option<PairState> {
+        self.lock()
+            .entries
+            .get(&addr)
+            .map(|entry: &Entry| entry.state)
+    }
+
+    /// Find address of commissioned device
+    /// -`id`       - device id
+    ///
+    /// -`return`   - device address, or None, if device is not commissioned
+    pub fn addr_of(&self, id: DeviceId) -> Option<RadioAddr> {
+        self.lock()
+            .entries
+            .iter()
+            .find(|(_, entry)| PairState::Commissioned(id) == entry.state)
+            .map(|(addr, _)| *addr)
+    }
+}
+
+impl Registry {
+    /// Drop pairing of the address
+    fn forget(&mut self, addr: RadioAddr) {
+        if let Some(entry) = self.entries.get_mut(&addr) {
+            entry.state = PairState::Unpaired;
+            entry.backend = None;
+        }
+    }
+}
+
+/// Link of commissioned device to its registry
+///
+/// -`registry` - registry, device is not kept alive by it
+/// -`addr`     - device address
+pub(crate) struct Registration {
+    registry: Weak<Mutex<Registry>>,
+    addr: RadioAddr,
+}
+
+impl Registration {
+    /// Inform registry, that device is dropped,
+    /// pairing is dropped, if it is still used by the device
+    /// -`id`       - dropped device id
+    pub(crate) fn release(self, id: DeviceId) {
+        let Some(registry) = self.registry.upgrade() else {
+            return;
+        };
+        let mut registry = registry.lock().unwrap_or_else(|err| err.into_inner());
+        let state: Option<PairState> = registry.entries.get(&self.addr).map(|entry| entry.state);
+        if Some(PairState::Commissioned(id)) == state {
+            /* device out of range stays paired on its side */
+            let _ = registry.radio.unpair(self.addr);
+            registry.forget(self.addr);
+        }
+    }
+
+    /// Device address
+    pub(crate) fn addr(&self) -> RadioAddr {
+        self.addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{BackendError, SimBackend};
+    use crate::radio::{RadioAddr, RadioError, RadioKind, SimRadio};
+    use crate::registry::{DeviceRegistry, PairState};
+    use crate::{SmartDevice, SmartDeviceAccess, SmartSocket};
+
+    const SOCKET: RadioAddr = RadioAddr(0x0000_0a1b_2c3d_4e01);
+    const THERMOMETER: RadioAddr = RadioAddr(0x0000_0a1b_2c3d_4e02);
+
+    fn registry() -> DeviceRegistry {
+        let radio: SimRadio = SimRadio::new()
+            .with_device(SOCKET, RadioKind::Socket, SimBackend::new(1))
+            .with_device(THERMOMETER, RadioKind::Thermometer, SimBackend::new(2));
+        DeviceRegistry::new(radio)
+    }
+
+    #[test]
+    fn lifecycle_tests() {
+        let registry: DeviceRegistry = registry();
+        assert_eq!(None, registry.state(SOCKET));
+        assert_eq!(2, registry.discover().unwrap().len());
+        assert_eq!(Some(PairState::Discovered), registry.state(SOCKET));
+        assert_eq!(
+            Err(RadioError::State(SOCKET, PairState::Discovered)),
+            registry.commission(SOCKET, "kitchen").map(|_| ())
+        );
+
+        registry.pair(SOCKET).unwrap();
+        assert_eq!(
+            Err(RadioError::State(SOCKET, PairState::Paired)),
+            registry.pair(SOCKET)
+        );
+        /* paired device is not advertised */
+        assert_eq!(1, registry.discover().unwrap().len());
+
+        let mut dev: SmartDevice = registry.commission(SOCKET, "kitchen").unwrap();
+        assert_eq!("socket", dev.kind());
+        assert_eq!(
+            Some(PairState::Commissioned(dev.id())),
+            registry.state(SOCKET)
+        );
+        assert_eq!(Some(SOCKET), registry.addr_of(dev.id()));
+        assert!(dev.switch(true));
+        let socket: &mut SmartSocket = dev.downcast_mut().unwrap();
+        assert_eq!(Some(SOCKET), socket.radio_addr());
+
+        /* unpaired device is kept, but it does not answer */
+        registry.unpair(SOCKET).unwrap();
+        assert_eq!(Some(PairState::Unpaired), registry.state(SOCKET));
+        assert_eq!(Err(BackendError::Offline), socket.try_dis());
+        assert_eq!(
+            Err(RadioError::State(SOCKET, PairState::Unpaired)),
+            registry.unpair(SOCKET)
+        );
+        drop(dev);
+        assert_eq!(Some(PairState::Unpaired), registry.state(SOCKET));
+        assert_eq!(2, registry.discover().unwrap().len());
+    }
+
+    #[test]
+    fn lifecycle_drop_tests() {
+        let registry: DeviceRegistry = registry();
+        registry.discover().unwrap();
+        registry.pair(THERMOMETER).unwrap();
+        let mut dev: SmartDevice = registry.commission(THERMOMETER, "hall").unwrap();
+        dev.update();
+        assert_eq!("name: hall\ntemp: 293.15 K", dev.status());
+
+        /* copy is not commissioned, its drop keeps the pairing */
+        drop(dev.clone());
+        assert_eq!(
+            Some(PairState::Commissioned(dev.id())),
+            registry.state(THERMOMETER)
+        );
+        drop(dev);
+        assert_eq!(Some(PairState::Unpaired), registry.state(THERMOMETER));
+
+        /* device may outlive its registry */
+        registry.discover().unwrap();
+        registry.pair(SOCKET).unwrap();
+        let dev: SmartDevice = registry.commission(SOCKET, "kitchen").unwrap();
+        drop(registry);
+        drop(dev);
+    }
+}
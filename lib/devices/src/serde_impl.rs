@@ -0,0 +1,56 @@
+//! Serde support for smart devices
+//! built-in device types keep the format of
+//! the former `SmartDevice` enum, externally tagged.
+//! Devices from other crates are not serializable.
+use crate::device::Nodev;
+use crate::smartsocket::SmartSocket;
+use crate::smartthermometer::SmartThermometer;
+use crate::SmartDevice;
+use serde::ser::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialized device
+#[derive(Serialize)]
+#[serde(rename = "SmartDevice")]
+enum DeviceRef<'a> {
+    Nodev,
+    Socket(&'a SmartSocket),
+    Thermometer(&'a SmartThermometer),
+}
+
+/// Deserialized device
+#[derive(Deserialize)]
+#[serde(rename = "SmartDevice")]
+enum DeviceOwned {
+    Nodev,
+    Socket(SmartSocket),
+    Thermometer(SmartThermometer),
+}
+
+impl Serialize for SmartDevice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let dev: DeviceRef = if self.is_nodev() {
+            DeviceRef::Nodev
+        } else if let Some(socket) = self.downcast_ref::<SmartSocket>() {
+            DeviceRef::Socket(socket)
+        } else if let Some(thermometer) = self.downcast_ref::<SmartThermometer>() {
+            DeviceRef::Thermometer(thermometer)
+        } else {
+            return Err(S::Error::custom(format!(
+                "device kind {} is not serializable",
+                self.kind()
+            )));
+        };
+        dev.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SmartDevice {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match DeviceOwned::deserialize(deserializer)? {
+            DeviceOwned::Nodev => SmartDevice::new(Nodev),
+            DeviceOwned::Socket(socket) => SmartDevice::new(socket),
+            DeviceOwned::Thermometer(thermometer) => SmartDevice::new(thermometer),
+        })
+    }
+}
@@ -1,102 +1,320 @@
 //! Module for Smarthome Socket
 //! can enable/disable
 //! show status
+use crate::backend::{self, BackendError, SharedBackend};
+use crate::device::{Capabilities, Device};
+use crate::id::DeviceId;
+use crate::locale::{self, Catalog, MsgKey};
+use crate::radio::RadioAddr;
+use crate::registry::Registration;
+use crate::status::{DeviceState, DeviceStatus};
+use crate::units::{Energy, Power};
 use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::time::Instant;
 
 /// Smarthome socket
 ///
+/// -`id`           - unique device id
 /// -`text`         - device description
 /// -`enabled`      - device is enabled
 /// -`power`        - current power consumption
-#[allow(non_snake_case)] //to avoid rust erratic warning about milliwatt abbreviation
+/// -`energy`       - energy consumed until `metered`
+/// -`metered`      - time of the last energy metering
+/// -`backend`      - hardware access, shared with clones
+/// -`registration` - pairing of the commissioned device
 #[repr(C)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SocketRecord")
+)]
 pub struct SmartSocket {
-    pub text: String,  // device description
-    pub enabled: bool, // device is enabled
-    pub power_mW: u32, // device power consuption in mW
+    id: DeviceId,       // unique device id
+    pub text: String,   // device description
+    pub enabled: bool,  // device is enabled
+    pub power: Power,   // device power consuption
+    pub energy: Energy, // consumed energy of billing period
+    #[cfg_attr(feature = "serde", serde(skip))]
+    metered: Option<Instant>, // last energy metering
+    #[cfg_attr(feature = "serde", serde(skip))]
+    backend: SharedBackend, // hardware access
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) registration: Option<Registration>, // radio pairing
+}
+
+/// Stored socket, hardware is not stored
+///
+/// -`id`           - device id, new one, if it is not stored
+/// -`text`         - device description
+/// -`enabled`      - device is enabled
+/// -`power`        - last power consumption
+/// -`energy`       - consumed energy of billing period
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SocketRecord {
+    #[serde(default)]
+    id: Option<DeviceId>,
+    text: String,
+    enabled: bool,
+    power: Power,
+    energy: Energy,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SocketRecord> for SmartSocket {
+    type Error = &'static str;
+
+    /// Restored socket is simulated, simulation takes the stored state
+    fn try_from(record: SocketRecord) -> Result<Self, Self::Error> {
+        let id: DeviceId = match record.id {
+            Some(id) => id,
+            None => DeviceId::try_fresh().ok_or("device ids are exhausted")?,
+        };
+        let mut socket: SmartSocket = SmartSocket::with_id(id, &record.text, backend::simulated());
+        socket.enabled = record.enabled;
+        socket.power = record.power;
+        socket.energy = record.energy;
+        socket.sync_backend();
+        Ok(socket)
+    }
 }
-#[warn(non_snake_case)]
 
 impl SmartSocket {
     /*** interface ***/
 
-    /// Socket ctor
+    /// Socket ctor, socket is simulated until backend is set
     ///
     /// -`socket_text`  - socket description
     ///
     /// -`return`     - new socket instance
     pub fn new(socket_text: &str) -> Self {
+        Self::with_backend(socket_text, backend::simulated())
+    }
+
+    /// Socket ctor
+    ///
+    /// -`socket_text`  - socket description
+    /// -`backend`      - socket hardware access
+    ///
+    /// -`return`     - new socket instance
+    pub fn with_backend(socket_text: &str, backend: SharedBackend) -> Self {
+        Self::with_id(DeviceId::fresh(), socket_text, backend)
+    }
+
+    /// Socket ctor with the given id
+    fn with_id(id: DeviceId, socket_text: &str, backend: SharedBackend) -> Self {
         SmartSocket {
+            id,
             text: socket_text.to_string(),
             enabled: false,
-            power_mW: 0,
+            power: Power::ZERO,
+            energy: Energy::ZERO,
+            metered: None,
+            backend,
+            registration: None,
         }
     }
 
-    /// Enable socket
+    /// Get hardware access
+    pub fn backend(&self) -> SharedBackend {
+        self.backend.clone()
+    }
+
+    /// Get radio address
+    /// -`return`     - address, or None, if socket is not commissioned
+    pub fn radio_addr(&self) -> Option<RadioAddr> {
+        self.registration.as_ref().map(Registration::addr)
+    }
+
+    /// Set hardware access, known state is pushed to the new hardware
+    pub fn set_backend(&mut self, backend: SharedBackend) {
+        self.backend = backend;
+        self.sync_backend();
+    }
+
+    /// Switch hardware to the known state, hardware error is skipped,
+    /// so the state is read back by the next update
+    fn sync_backend(&self) {
+        let enabled: bool = self.enabled;
+        let _ = backend::with(&self.backend, |hw| hw.switch(enabled));
+    }
+
+    /// Enable socket, state is kept, if socket is not switched
     pub fn en(&mut self) {
+        let _ = self.try_en();
+    }
+
+    /// Disable socket, state is kept, if socket is not switched
+    pub fn dis(&mut self) {
+        let _ = self.try_dis();
+    }
+
+    /// Update socket status, state is kept, if it can't be read
+    pub fn update(&mut self) {
+        let _ = self.try_update();
+    }
+
+    /// Enable socket
+    /// -`return`     - hardware error, if socket is not switched
+    pub fn try_en(&mut self) -> Result<(), BackendError> {
         if !self.enabled {
+            backend::with(&self.backend, |hw| hw.switch(true))?;
+            self.meter(Instant::now());
             self.enabled = true;
-            self.power_mW = 0;
+            self.power = Power::ZERO;
         }
+        Ok(())
     }
 
     /// Disable socket
-    pub fn dis(&mut self) {
+    /// -`return`     - hardware error, if socket is not switched
+    pub fn try_dis(&mut self) -> Result<(), BackendError> {
         if self.enabled {
+            backend::with(&self.backend, |hw| hw.switch(false))?;
+            self.meter(Instant::now());
             self.enabled = false;
-            self.power_mW = 0;
+            self.power = Power::ZERO;
         }
+        Ok(())
     }
 
     /// Update socket status
-    pub fn update(&mut self) {
-        /* need to update the current state of soket,
-         * if user manually swith its state
-         */
-        if self.enabled {
-            /* need to get current power consumption of socket,
-             * if it changed
+    /// -`return`     - hardware error, if state can't be read
+    pub fn try_update(&mut self) -> Result<(), BackendError> {
+        /* energy is consumed with the previous power */
+        self.meter(Instant::now());
+        let known: bool = self.enabled;
+        let (enabled, power) = backend::with(&self.backend, |hw| {
+            /* user may manually switch the socket,
+             * relay without feedback keeps known state
              */
-            self.power_mW = 10000;
+            let enabled: bool = match hw.enabled() {
+                Err(BackendError::Unsupported) => known,
+                state => state?,
+            };
+            let power: Power = if enabled { hw.power()? } else { Power::ZERO };
+            Ok((enabled, power))
+        })?;
+        self.enabled = enabled;
+        self.power = power;
+        Ok(())
+    }
+
+    /// Add energy, consumed with current power since the last metering,
+    /// time before the last metering is skipped
+    /// -`now`      - metering time
+    pub fn meter(&mut self, now: Instant) {
+        match self.metered {
+            Some(since) if now < since => return,
+            Some(since) => {
+                let used: Energy = self.power.energy(now - since);
+                self.energy = self.energy.saturating_add(used);
+            }
+            None => {}
+        }
+        self.metered = Some(now);
+    }
+
+    /// Start new billing period
+    /// -`return`     - energy, consumed in the closed period
+    pub fn reset_energy(&mut self) -> Energy {
+        self.meter(Instant::now());
+        mem::take(&mut self.energy)
+    }
+}
+
+impl Device for SmartSocket {
+    fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    fn name_str(&self) -> &str {
+        &self.text
+    }
+
+    fn kind(&self) -> &'static str {
+        "socket"
+    }
+
+    fn report(&self) -> DeviceStatus {
+        let state: DeviceState = DeviceState::Socket {
+            enabled: self.enabled,
+            power: self.power,
+            energy: self.energy,
+        };
+        DeviceStatus::new(&self.text, state)
+    }
+
+    fn update(&mut self) {
+        SmartSocket::update(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::SWITCH | Capabilities::POWER
+    }
+
+    fn switch(&mut self, enable: bool) -> bool {
+        if enable {
+            self.en();
         } else {
-            self.power_mW = 0;
+            self.dis();
         }
+        true
     }
 }
 
 impl Drop for SmartSocket {
     /// Delete socket from network
     fn drop(&mut self) {
-        /* here should be radio network
-         * actions to inform the socket
-         * about fact, that is not used anymore
+        /* registry informs the socket over radio network,
+         * that it is not used anymore
          */
+        if let Some(registration) = self.registration.take() {
+            registration.release(self.id);
+        }
     }
 }
 
 impl fmt::Display for SmartSocket {
     /// Socket print implementation
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let state_string: &'static str = if self.enabled { "On" } else { "Off" };
-        let out_s = &format!(
-            "Name = {}\tState = {}\tPower = {} mW",
-            self.text, state_string, self.power_mW
-        );
-        fmt.write_str(out_s)?;
-        Ok(())
+        let catalog: Arc<Catalog> = locale::current();
+        let state_string: &str = catalog.get(if self.enabled {
+            MsgKey::LabelOn
+        } else {
+            MsgKey::LabelOff
+        });
+        write!(
+            fmt,
+            "{} = {}\t{} = {}\t{} = {} {}",
+            catalog.get(MsgKey::LabelName),
+            self.text,
+            catalog.get(MsgKey::LabelState),
+            state_string,
+            catalog.get(MsgKey::LabelPower),
+            self.power.milliwatts(),
+            catalog.get(MsgKey::Milliwatt)
+        )
     }
 }
 
 /* linked list have special clone rules */
 impl Clone for SmartSocket {
-    /// To copy smartsocket need to copy it's fields
-    /// care! if we add the uid, this will be changed
+    /// To copy smartsocket need to copy it's fields,
+    /// copy controls the same hardware, but it is other device,
+    /// so it takes new id and it is not commissioned
     fn clone(&self) -> Self {
         SmartSocket {
+            id: DeviceId::fresh(),
             text: self.text.clone(),
             enabled: self.enabled,
-            power_mW: self.power_mW,
+            power: self.power,
+            energy: self.energy,
+            metered: self.metered,
+            backend: self.backend.clone(),
+            registration: None,
         }
     }
 }
@@ -105,16 +323,24 @@ impl Default for SmartSocket {
     /// Default is disabled socket with name smart socket
     fn default() -> Self {
         SmartSocket {
+            id: DeviceId::fresh(),
             text: "smart socket".to_string(),
             enabled: false,
-            power_mW: 0,
+            power: Power::ZERO,
+            energy: Energy::ZERO,
+            metered: None,
+            backend: backend::simulated(),
+            registration: None,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::backend::{self, SimBackend};
+    use crate::units::{Energy, Power};
     use crate::SmartSocket;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_socket() {
@@ -124,6 +350,45 @@ mod test {
         socket0.dis();
         assert!(!socket0.enabled);
         socket0.update();
-        assert_eq!(0, socket0.power_mW);
+        assert_eq!(Power::ZERO, socket0.power);
+        assert_eq!(
+            "Name = Socket in kitchen\tState = Off\tPower = 0 mW",
+            socket0.to_string()
+        );
+    }
+
+    #[test]
+    fn test_socket_backend() {
+        /* new hardware takes the known state */
+        let mut socket0 = SmartSocket::new("Socket in kitchen");
+        socket0.en();
+        socket0.set_backend(backend::shared(SimBackend::new(7)));
+        assert_eq!(Ok(true), backend::with(&socket0.backend, |hw| hw.enabled()));
+        socket0.update();
+        assert!(socket0.enabled);
+    }
+
+    #[test]
+    fn test_socket_energy() {
+        let start: Instant = Instant::now();
+        let hour: Duration = Duration::from_secs(3600);
+        let mut socket0 = SmartSocket::new("Socket in kitchen");
+        socket0.meter(start);
+        socket0.enabled = true;
+        socket0.power = Power::from_watts(100.0).unwrap();
+        socket0.meter(start + hour);
+        assert_eq!(Some(socket0.energy), Energy::from_watt_hours(100.0));
+
+        /* toggles keep the counter, metering never goes back */
+        socket0.dis();
+        socket0.en();
+        assert_eq!(Some(socket0.energy), Energy::from_watt_hours(100.0));
+        socket0.power = Power::from_kilowatts(1.0).unwrap();
+        socket0.meter(start + hour * 3);
+        assert_eq!(Some(socket0.energy), Energy::from_kilowatt_hours(2.1));
+
+        /* new billing period */
+        assert!(socket0.reset_energy() >= Energy::from_kilowatt_hours(2.1).unwrap());
+        assert_eq!(Energy::ZERO, socket0.energy);
     }
 }
@@ -1,66 +1,211 @@
 //! Module for Smarthome Thermometer
 //! show status
+use crate::backend::{self, BackendError, SharedBackend};
+use crate::device::{Capabilities, Device};
+use crate::id::DeviceId;
+use crate::locale::{self, Catalog, MsgKey};
+use crate::radio::RadioAddr;
+use crate::registry::Registration;
+use crate::status::{DeviceState, DeviceStatus};
+use crate::units::{TempUnit, Temperature};
 use std::fmt;
+use std::sync::Arc;
 
 /// Smarthome thermometer
 ///
+/// -`id`    - unique device id
 /// -`text`  - device description
-/// -`temp`  - current temperature in °K
+/// -`temp`  - current temperature
+/// -`unit`  - temperature display unit
+/// -`backend`  - hardware access, shared with clones
+/// -`registration` - pairing of the commissioned device
 #[repr(C)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ThermometerRecord")
+)]
 pub struct SmartThermometer {
-    pub text: String, // device description
-    pub temp: u16,    // device temperature in °K
+    id: DeviceId,          // unique device id
+    pub text: String,      // device description
+    pub temp: Temperature, // device temperature
+    pub unit: TempUnit,    // temperature display unit
+    #[cfg_attr(feature = "serde", serde(skip))]
+    backend: SharedBackend, // hardware access
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) registration: Option<Registration>, // radio pairing
 }
 
+/// Stored thermometer, hardware is not stored
+///
+/// -`id`    - device id, new one, if it is not stored
+/// -`text`  - device description
+/// -`temp`  - last temperature
+/// -`unit`  - temperature display unit
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ThermometerRecord {
+    #[serde(default)]
+    id: Option<DeviceId>,
+    text: String,
+    temp: Temperature,
+    unit: TempUnit,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ThermometerRecord> for SmartThermometer {
+    type Error = &'static str;
+
+    /// Restored thermometer is simulated
+    fn try_from(record: ThermometerRecord) -> Result<Self, Self::Error> {
+        let id: DeviceId = match record.id {
+            Some(id) => id,
+            None => DeviceId::try_fresh().ok_or("device ids are exhausted")?,
+        };
+        let mut thermometer: SmartThermometer =
+            SmartThermometer::with_id(id, &record.text, backend::simulated());
+        thermometer.temp = record.temp;
+        thermometer.unit = record.unit;
+        Ok(thermometer)
+    }
+}
+
+/// Temperature of the new thermometer
+const INIT_TEMP: Temperature = Temperature::ZERO_CELSIUS;
+
 impl SmartThermometer {
     /*** interface ***/
 
-    /// Thermometer ctor
+    /// Thermometer ctor, thermometer is simulated until backend is set
     ///
     /// -`thermometer_text`  - thermometer description
     ///
-    /// -`return`     - new socket instance
+    /// -`return`     - new thermometer instance
     pub fn new(thermometer_text: &str) -> Self {
+        Self::with_backend(thermometer_text, backend::simulated())
+    }
+
+    /// Thermometer ctor
+    ///
+    /// -`thermometer_text`  - thermometer description
+    /// -`backend`           - thermometer hardware access
+    ///
+    /// -`return`     - new thermometer instance
+    pub fn with_backend(thermometer_text: &str, backend: SharedBackend) -> Self {
+        Self::with_id(DeviceId::fresh(), thermometer_text, backend)
+    }
+
+    /// Thermometer ctor with the given id
+    fn with_id(id: DeviceId, thermometer_text: &str, backend: SharedBackend) -> Self {
         SmartThermometer {
+            id,
             text: thermometer_text.to_string(),
-            temp: 273,
+            temp: INIT_TEMP,
+            unit: TempUnit::Kelvin,
+            backend,
+            registration: None,
         }
     }
 
-    /// Update thermometer status
+    /// Get hardware access
+    pub fn backend(&self) -> SharedBackend {
+        self.backend.clone()
+    }
+
+    /// Get radio address
+    /// -`return`     - address, or None, if thermometer is not commissioned
+    pub fn radio_addr(&self) -> Option<RadioAddr> {
+        self.registration.as_ref().map(Registration::addr)
+    }
+
+    /// Set hardware access
+    pub fn set_backend(&mut self, backend: SharedBackend) {
+        self.backend = backend;
+    }
+
+    /// Update thermometer status, last reading is kept, if it can't be read
     pub fn update(&mut self) {
-        /* need to update the current state of thermometer */
-        self.temp = 273 + 20;
+        let _ = self.try_update();
+    }
+
+    /// Update thermometer status
+    /// -`return`     - hardware error, if temperature can't be read
+    pub fn try_update(&mut self) -> Result<(), BackendError> {
+        self.temp = backend::with(&self.backend, |hw| hw.temperature())?;
+        Ok(())
+    }
+}
+
+impl Device for SmartThermometer {
+    fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    fn name_str(&self) -> &str {
+        &self.text
+    }
+
+    fn kind(&self) -> &'static str {
+        "thermometer"
+    }
+
+    fn report(&self) -> DeviceStatus {
+        let state: DeviceState = DeviceState::Thermometer {
+            temp: self.temp,
+            unit: self.unit,
+        };
+        DeviceStatus::new(&self.text, state)
+    }
+
+    fn update(&mut self) {
+        SmartThermometer::update(self)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::TEMPERATURE
     }
 }
 
 impl Drop for SmartThermometer {
     /// Delete thermometer from network
     fn drop(&mut self) {
-        /* here should be radio network
-         * actions to infrom the thermometer
-         * about fact, that is not used anymore
+        /* registry informs the thermometer over radio network,
+         * that it is not used anymore
          */
+        if let Some(registration) = self.registration.take() {
+            registration.release(self.id);
+        }
     }
 }
 
 impl fmt::Display for SmartThermometer {
     /// Thermometer print implementation
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let out_s = &format!("Name = {}\tTemp = {}", self.text, self.temp);
-        fmt.write_str(out_s)?;
-        Ok(())
+        let catalog: Arc<Catalog> = locale::current();
+        write!(
+            fmt,
+            "{} = {}\t{} = {}",
+            catalog.get(MsgKey::LabelName),
+            self.text,
+            catalog.get(MsgKey::LabelTemp),
+            self.temp.display(self.unit)
+        )
     }
 }
 
 /* linked list have special clone rules */
 impl Clone for SmartThermometer {
-    /// To copy smartthermometer need to copy it's fields
-    /// care! if we add the uid, this will be changed
+    /// To copy smartthermometer need to copy it's fields,
+    /// copy reads the same hardware, but it is other device,
+    /// so it takes new id and it is not commissioned
     fn clone(&self) -> Self {
         SmartThermometer {
+            id: DeviceId::fresh(),
             text: self.text.clone(),
             temp: self.temp,
+            unit: self.unit,
+            backend: self.backend.clone(),
+            registration: None,
         }
     }
 }
@@ -69,8 +214,12 @@ impl Default for SmartThermometer {
     /// Default is smartthermometer with name smart thermometer
     fn default() -> Self {
         SmartThermometer {
+            id: DeviceId::fresh(),
             text: "smart thermometer".to_string(),
-            temp: 273,
+            temp: INIT_TEMP,
+            unit: TempUnit::Kelvin,
+            backend: backend::simulated(),
+            registration: None,
         }
     }
 }
@@ -0,0 +1,183 @@
+//! Structured device status
+//! device reports its state as data,
+//! the text status is rendered from it
+use crate::locale::{self, Catalog, MsgKey};
+use crate::units::{Energy, Power, TempUnit, Temperature};
+use std::fmt;
+use std::time::SystemTime;
+
+/// Device type specific state
+///
+/// -`Nodev`        - not a device
+/// -`Socket`       - switch state, power and energy consumption
+/// -`Thermometer`  - temperature reading and its display unit
+/// -`Other`        - state of a device from other crate, as text
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeviceState {
+    Nodev,
+    Socket {
+        enabled: bool,
+        power: Power,
+        energy: Energy,
+    },
+    Thermometer {
+        temp: Temperature,
+        unit: TempUnit,
+    },
+    Other(String),
+}
+
+/// Device status snapshot
+///
+/// -`name`     - device name
+/// -`state`    - device state
+/// -`time`     - time, when status was taken
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceStatus {
+    pub name: String,
+    pub state: DeviceState,
+    pub time: SystemTime,
+}
+
+impl DeviceStatus {
+    /// Status ctor, status is taken now
+    ///
+    /// -`name`     - device name
+    /// -`state`    - device state
+    ///
+    /// -`return`   - new status
+    pub fn new(name: &str, state: DeviceState) -> Self {
+        DeviceStatus {
+            name: name.to_string(),
+            state,
+            time: SystemTime::now(),
+        }
+    }
+}
+
+impl DeviceStatus {
+    /// Write status text of the locale
+    /// -`catalog`  - locale messages
+    pub fn write_to(&self, fmt: &mut dyn fmt::Write, catalog: &Catalog) -> fmt::Result {
+        let name: &str = catalog.get(MsgKey::Name);
+        match &self.state {
+            DeviceState::Nodev => fmt.write_str(catalog.get(MsgKey::NotDevice)),
+            DeviceState::Socket {
+                enabled,
+                power,
+                energy,
+            } => {
+                let status: &str = catalog.get(MsgKey::Status);
+                write!(fmt, "{}: {}\n{}: ", name, self.name, status)?;
+                if *enabled {
+                    write!(
+                        fmt,
+                        "{}\n{}: {} {}",
+                        catalog.get(MsgKey::On),
+                        catalog.get(MsgKey::Power),
+                        power.milliwatts(),
+                        catalog.get(MsgKey::Milliwatt)
+                    )?;
+                } else {
+                    fmt.write_str(catalog.get(MsgKey::Off))?;
+                }
+                write!(
+                    fmt,
+                    "\n{}: {:.3} {}",
+                    catalog.get(MsgKey::Energy),
+                    energy.watt_hours(),
+                    catalog.get(MsgKey::WattHour)
+                )
+            }
+            DeviceState::Thermometer { temp, unit } => {
+                let temp_str: &str = catalog.get(MsgKey::Temp);
+                let temp = temp.display(*unit);
+                write!(fmt, "{}: {}\n{}: {}", name, self.name, temp_str, temp)
+            }
+            DeviceState::Other(text) => write!(fmt, "{}: {}\n{}", name, self.name, text),
+        }
+    }
+
+    /// Status text of the locale
+    /// -`catalog`  - locale messages
+    ///
+    /// -`return`   - human readable status, one field per line
+    pub fn render(&self, catalog: &Catalog) -> String {
+        let mut text: String = String::new();
+        /* write into string never fails */
+        let _ = self.write_to(&mut text, catalog);
+        text
+    }
+}
+
+impl fmt::Display for DeviceStatus {
+    /// Human readable status of the current locale
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(fmt, &locale::current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::Catalog;
+    use crate::smartsocket::SmartSocket;
+    use crate::smartthermometer::SmartThermometer;
+    use crate::status::{DeviceState, DeviceStatus};
+    use crate::units::{Energy, Power, TempUnit, Temperature};
+    use crate::{Device, SmartDevice};
+    use std::time::SystemTime;
+
+    #[test]
+    fn status_tests() {
+        let mut socket: SmartSocket = SmartSocket::new("socket0");
+        let before: SystemTime = SystemTime::now();
+        let status: DeviceStatus = socket.report();
+        assert!(before <= status.time);
+        assert_eq!(
+            DeviceState::Socket {
+                enabled: false,
+                power: Power::ZERO,
+                energy: Energy::ZERO
+            },
+            status.state
+        );
+        assert_eq!(
+            "name: socket0\nstatus: off\nenergy: 0.000 Wh",
+            status.to_string()
+        );
+
+        socket.en();
+        socket.update();
+        assert_eq!(
+            "name: socket0\nstatus: on\npower: 10000 mW\nenergy: 0.000 Wh",
+            socket.status()
+        );
+
+        let mut thermometer: SmartThermometer = SmartThermometer::new("thermometer0");
+        thermometer.update();
+        let status: DeviceStatus = thermometer.report();
+        assert_eq!(
+            DeviceState::Thermometer {
+                temp: Temperature::from_celsius(20.0).unwrap(),
+                unit: TempUnit::Kelvin
+            },
+            status.state
+        );
+        assert_eq!("name: thermometer0\ntemp: 293.15 K", status.to_string());
+        thermometer.unit = TempUnit::Celsius;
+        assert_eq!("name: thermometer0\ntemp: 20 °C", thermometer.status());
+        assert_eq!("not device", SmartDevice::nodev().report().to_string());
+
+        let ru: Catalog = Catalog::builtin("ru").unwrap();
+        assert_eq!(
+            "имя: thermometer0\nтемпература: 293.15 K",
+            status.render(&ru)
+        );
+        assert_eq!(
+            "имя: socket0\nсостояние: вкл\nмощность: 10000 мВт\nэнергия: 0.000 Вт·ч",
+            socket.report().render(&ru)
+        );
+    }
+}
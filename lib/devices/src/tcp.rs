@@ -0,0 +1,415 @@
+//! Networked smart socket
+//! `SocketServer` serves one `SmartSocket` over TCP,
+//! `SocketClient` is a `DeviceBackend`, so remote socket
+//! is a `SmartSocket` with the client as its hardware.
+//! Frames are described in `proto`.
+use crate::backend::{BackendError, DeviceBackend};
+use crate::proto::{ErrorCode, ProtoError, Request, Response};
+use crate::smartsocket::SmartSocket;
+use crate::units::Power;
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Client request timeout
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Socket, served to network
+///
+/// -`listener` - bound listener
+/// -`socket`   - served socket, shared with connections
+pub struct SocketServer {
+    listener: TcpListener,
+    socket: Arc<Mutex<SmartSocket>>,
+}
+
+impl SocketServer {
+    /// Server ctor
+    ///
+    /// -`addr`     - listen address, port 0 picks free port
+    /// -`socket`   - socket to serve
+    ///
+    /// -`return`   - bound server, or bind error
+    pub fn bind<A: ToSocketAddrs>(addr: A, socket: SmartSocket) -> io::Result<Self> {
+        Ok(SocketServer {
+            listener: TcpListener::bind(addr)?,
+            socket: Arc::new(Mutex::new(socket)),
+        })
+    }
+
+    /// Listen address
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Served socket
+    pub fn socket(&self) -> Arc<Mutex<SmartSocket>> {
+        self.socket.clone()
+    }
+
+    /// Serve connections in background thread,
+    /// every connection is served by its own thread
+    /// -`return`   - server handle, server is stopped, when it is dropped
+    pub fn spawn(self) -> io::Result<ServerHandle> {
+        let addr: SocketAddr = self.local_addr()?;
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let flag: Arc<AtomicBool> = stop.clone();
+        let thread: JoinHandle<()> = thread::spawn(move || self.serve(&flag));
+        Ok(ServerHandle {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Accept connections, until stop flag is set
+    fn serve(&self, stop: &AtomicBool) {
+        for stream in self.listener.incoming() {
+            if stop.load(Ordering::Acquire) {
+                break;
+            }
+            /* failed accept drops only that connection */
+            if let Ok(stream) = stream {
+                let socket: Arc<Mutex<SmartSocket>> = self.socket.clone();
+                thread::spawn(move || serve_connection(stream, &socket));
+            }
+        }
+    }
+}
+
+/// Serve requests of one client, until it disconnects
+fn serve_connection(mut stream: TcpStream, socket: &Mutex<SmartSocket>) {
+    let _ = stream.set_nodelay(true);
+    loop {
+        let response: Response = match Request::read(&mut stream) {
+            Ok(request) => {
+                let mut socket = socket.lock().unwrap_or_else(|err| err.into_inner());
+                execute(&mut socket, request)
+            }
+            Err(ProtoError::Version(_)) => Response::Error {
+                code: ErrorCode::Version,
+                text: format!("protocol version {} is supported", crate::proto::VERSION),
+            },
+            Err(ProtoError::Io(_)) => return,
+            Err(err) => Response::Error {
+                code: ErrorCode::Request,
+                text: err.to_string(),
+            },
+        };
+        if response.write(&mut stream).is_err() {
+            return;
+        }
+    }
+}
+
+/// Run request on the socket
+fn execute(socket: &mut SmartSocket, request: Request) -> Response {
+    let done: Result<Response, BackendError> = match request {
+        Request::Enable => socket.try_en().map(|_| Response::Done),
+        Request::Disable => socket.try_dis().map(|_| Response::Done),
+        Request::Status => socket.try_update().map(|_| Response::Status {
+            enabled: socket.enabled,
+            power: socket.power,
+            energy: socket.energy,
+        }),
+        Request::Power => socket.try_update().map(|_| Response::Power(socket.power)),
+    };
+    done.unwrap_or_else(|err| Response::Error {
+        code: ErrorCode::Device,
+        text: err.to_string(),
+    })
+}
+
+/// Running server
+///
+/// -`addr`     - listen address
+/// -`stop`     - stop flag
+/// -`thread`   - accept thread
+pub struct ServerHandle {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Listen address
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop accepting connections, connected clients are served,
+    /// until they disconnect
+    pub fn shutdown(mut self) {
+        self.stop_thread();
+    }
+
+    fn stop_thread(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            /* wake up accept */
+            let _ = TcpStream::connect(self.addr);
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// Map stream error to hardware error
+fn transport(err: ProtoError) -> BackendError {
+    match err {
+        ProtoError::Io(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            BackendError::Timeout
+        }
+        err => BackendError::Transport(err.to_string()),
+    }
+}
+
+/// Client of the socket server, connection is dropped
+/// after stream error, and the next request connects again
+///
+/// -`addr`     - server address
+/// -`timeout`  - request timeout
+/// -`stream`   - connection to server, None after stream error
+pub struct SocketClient {
+    addr: SocketAddr,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl SocketClient {
+    /// Connect to server
+    /// -`addr`     - server address
+    ///
+    /// -`return`   - connected client, or connect error
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream: TcpStream = TcpStream::connect(addr)?;
+        let mut client: SocketClient = SocketClient {
+            addr: stream.peer_addr()?,
+            timeout: TIMEOUT,
+            stream: None,
+        };
+        client.stream = Some(client.setup(stream)?);
+        Ok(client)
+    }
+
+    /// Set request timeout
+    /// -`timeout`  - read and write timeout, should not be zero
+    pub fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.timeout = timeout;
+        if let Some(stream) = self.stream.take() {
+            self.stream = Some(self.setup(stream)?);
+        }
+        Ok(())
+    }
+
+    /// Send request and wait for response,
+    /// client connects again, if previous request failed
+    /// -`return`   - server response, or stream error
+    pub fn request(&mut self, request: Request) -> Result<Response, ProtoError> {
+        let stream: &mut TcpStream = match self.stream {
+            Some(ref mut stream) => stream,
+            None => {
+                let stream: TcpStream = self.setup(TcpStream::connect(self.addr)?)?;
+                self.stream.insert(stream)
+            }
+        };
+        let response: Result<Response, ProtoError> =
+            request.write(stream).and_then(|_| Response::read(stream));
+        if let Err(ProtoError::Io(_)) = response {
+            /* late response would answer the next request */
+            self.disconnect();
+        }
+        response
+    }
+
+    /// Configure new connection
+    fn setup(&self, stream: TcpStream) -> io::Result<TcpStream> {
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+
+    /// Drop connection, if it is open
+    fn disconnect(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Send request, error response is hardware error
+    fn call(&mut self, request: Request) -> Result<Response, BackendError> {
+        match self.request(request).map_err(transport)? {
+            Response::Error { text, .. } => Err(BackendError::Transport(text)),
+            response => Ok(response),
+        }
+    }
+
+    /// Remote socket
+    /// -`socket_text`  - socket description
+    ///
+    /// -`return`       - socket, controlled through this client
+    pub fn into_socket(self, socket_text: &str) -> SmartSocket {
+        SmartSocket::with_backend(socket_text, crate::backend::shared(self))
+    }
+}
+
+impl Drop for SocketClient {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+impl DeviceBackend for SocketClient {
+    fn switch(&mut self, enable: bool) -> Result<(), BackendError> {
+        let request: Request = if enable {
+            Request::Enable
+        } else {
+            Request::Disable
+        };
+        match self.call(request)? {
+            Response::Done => Ok(()),
+            other => Err(BackendError::Transport(format!("unexpected {:?}", other))),
+        }
+    }
+
+    fn enabled(&mut self) -> Result<bool, BackendError> {
+        match self.call(Request::Status)? {
+            Response::Status { enabled, .. } => Ok(enabled),
+            other => Err(BackendError::Transport(format!("unexpected {:?}", other))),
+        }
+    }
+
+    fn power(&mut self) -> Result<Power, BackendError> {
+        match self.call(Request::Power)? {
+            Response::Power(power) => Ok(power),
+            other => Err(BackendError::Transport(format!("unexpected {:?}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{self, BackendError, SimBackend};
+    use crate::proto::{self, ErrorCode, Request, Response};
+    use crate::tcp::{ServerHandle, SocketClient, SocketServer};
+    use crate::units::Power;
+    use crate::{Device, SmartSocket};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    fn server(sim: &Arc<Mutex<SimBackend>>) -> ServerHandle {
+        let socket: SmartSocket = SmartSocket::with_backend("kitchen", sim.clone());
+        SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn tcp_socket_tests() {
+        let sim: Arc<Mutex<SimBackend>> = Arc::new(Mutex::new(SimBackend::new(1)));
+        let server: ServerHandle = server(&sim);
+        let mut remote: SmartSocket = SocketClient::connect(server.addr())
+            .unwrap()
+            .into_socket("remote kitchen");
+
+        /* remote socket is used as any device */
+        assert!(remote.switch(true));
+        remote.update();
+        assert!(remote.enabled);
+        assert_eq!(Power::from_milliwatts(10000), remote.power);
+
+        /* second client sees the same socket */
+        let mut client: SocketClient = SocketClient::connect(server.addr()).unwrap();
+        assert_eq!(Response::Done, client.request(Request::Disable).unwrap());
+        remote.update();
+        assert!(!remote.enabled);
+
+        sim.lock().unwrap().set_offline(true);
+        assert!(matches!(remote.try_en(), Err(BackendError::Transport(_))));
+        sim.lock().unwrap().set_offline(false);
+        server.shutdown();
+        /* connected clients are still served */
+        assert_eq!(Ok(()), remote.try_en());
+    }
+
+    #[test]
+    fn tcp_version_tests() {
+        let socket: SmartSocket = SmartSocket::with_backend("hall", backend::simulated());
+        let server: ServerHandle = SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap();
+        let mut stream: TcpStream = TcpStream::connect(server.addr()).unwrap();
+        stream.write_all(&[proto::VERSION + 1, 0x03, 0, 0]).unwrap();
+        match Response::read(&mut stream).unwrap() {
+            Response::Error { code, .. } => assert_eq!(ErrorCode::Version, code),
+            other => panic!("{:?}", other),
+        }
+        /* connection stays usable */
+        Request::Status.write(&mut stream).unwrap();
+        assert!(matches!(
+            Response::read(&mut stream).unwrap(),
+            Response::Status { enabled: false, .. }
+        ));
+        stream.write_all(&[proto::VERSION, 0x55, 0, 0]).unwrap();
+        match Response::read(&mut stream).unwrap() {
+            Response::Error { code, .. } => assert_eq!(ErrorCode::Request, code),
+            other => panic!("{:?}", other),
+        }
+        drop(server);
+        let mut rest: Vec<u8> = Vec::new();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        stream.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn tcp_timeout_tests() {
+        /* first connection answers too late, second one in time */
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        let server: JoinHandle<()> = thread::spawn(move || {
+            thread::scope(|scope| {
+                for (delay, mw) in [(300, 1), (0, 2)] {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    scope.spawn(move || {
+                        Request::read(&mut stream).unwrap();
+                        thread::sleep(Duration::from_millis(delay));
+                        let _ = Response::Power(Power::from_milliwatts(mw)).write(&mut stream);
+                    });
+                }
+            })
+        });
+
+        let mut client: SocketClient = SocketClient::connect(addr).unwrap();
+        client.set_timeout(Duration::from_millis(100)).unwrap();
+        assert!(matches!(
+            client.request(Request::Power),
+            Err(proto::ProtoError::Io(_))
+        ));
+        /* late answer is not taken as the answer to the next request */
+        assert_eq!(
+            Response::Power(Power::from_milliwatts(2)),
+            client.request(Request::Power).unwrap()
+        );
+        server.join().unwrap();
+    }
+}
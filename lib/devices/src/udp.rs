@@ -0,0 +1,406 @@
+//! Thermometer telemetry over UDP
+//! `TelemetryEmitter` pushes readings of a `SmartThermometer`,
+//! `TelemetryReceiver` is a `DeviceBackend`, so receiving
+//! thermometer is a `SmartThermometer` with the receiver as its hardware.
+//!
+//! every reading is one datagram:
+//! `version: u8, seq: u32, temp_mk: i64`, numbers are big endian.
+//! Sequence number wraps, datagrams older than the last one are dropped.
+use crate::backend::{BackendError, DeviceBackend};
+use crate::smartthermometer::SmartThermometer;
+use crate::units::Temperature;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Telemetry version
+pub const VERSION: u8 = 1;
+
+/// Datagram length
+pub const DATAGRAM_LEN: usize = 13;
+
+/// One reading
+///
+/// -`seq`      - sequence number of the emitter
+/// -`temp`     - temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Telemetry {
+    pub seq: u32,
+    pub temp: Temperature,
+}
+
+impl Telemetry {
+    /// Datagram of the reading
+    pub fn encode(&self) -> [u8; DATAGRAM_LEN] {
+        let mut datagram: [u8; DATAGRAM_LEN] = [0; DATAGRAM_LEN];
+        datagram[0] = VERSION;
+        datagram[1..5].copy_from_slice(&self.seq.to_be_bytes());
+        datagram[5..].copy_from_slice(&self.temp.millikelvin().to_be_bytes());
+        datagram
+    }
+
+    /// Reading of the datagram
+    /// -`datagram` - received bytes
+    ///
+    /// -`return`   - reading, `None` if datagram is malformed or of other version
+    pub fn decode(datagram: &[u8]) -> Option<Self> {
+        if DATAGRAM_LEN != datagram.len() || VERSION != datagram[0] {
+            return None;
+        }
+        let mut seq: [u8; 4] = [0; 4];
+        seq.copy_from_slice(&datagram[1..5]);
+        let mut mk: [u8; 8] = [0; 8];
+        mk.copy_from_slice(&datagram[5..]);
+        Some(Telemetry {
+            seq: u32::from_be_bytes(seq),
+            temp: Temperature::from_millikelvin(i64::from_be_bytes(mk))?,
+        })
+    }
+
+    /// Return true, if reading is sent after `other`, sequence may wrap
+    pub fn is_after(&self, other: &Telemetry) -> bool {
+        (self.seq.wrapping_sub(other.seq) as i32) > 0
+    }
+}
+
+/// Thermometer, that pushes its readings
+///
+/// -`socket`       - sending socket
+/// -`target`       - receiver or broadcast address
+/// -`seq`          - sequence number of the next datagram
+/// -`thermometer`  - read thermometer
+pub struct TelemetryEmitter {
+    socket: UdpSocket,
+    target: SocketAddr,
+    seq: u32,
+    thermometer: SmartThermometer,
+}
+
+impl TelemetryEmitter {
+    /// Emitter ctor
+    ///
+    /// -`addr`         - local address, port 0 picks free port
+    /// -`target`       - receiver address, may be broadcast
+    /// -`thermometer`  - thermometer to read
+    ///
+    /// -`return`       - bound emitter, or socket error
+    pub fn bind<A: ToSocketAddrs, T: ToSocketAddrs>(
+        addr: A,
+        target: T,
+        thermometer: SmartThermometer,
+    ) -> io::Result<Self> {
+        let target: SocketAddr = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no target address"))?;
+        let socket: UdpSocket = UdpSocket::bind(addr)?;
+        socket.set_broadcast(true)?;
+        Ok(TelemetryEmitter {
+            socket,
+            target,
+            seq: 0,
+            thermometer,
+        })
+    }
+
+    /// Read thermometer
+    pub fn thermometer(&self) -> &SmartThermometer {
+        &self.thermometer
+    }
+
+    /// Read thermometer and send the reading
+    /// -`return`   - sent reading, or error;
+    ///               failed reading is not sent and takes no sequence number
+    pub fn emit(&mut self) -> io::Result<Telemetry> {
+        self.thermometer
+            .try_update()
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        let telemetry: Telemetry = Telemetry {
+            seq: self.seq,
+            temp: self.thermometer.temp,
+        };
+        self.socket.send_to(&telemetry.encode(), self.target)?;
+        self.seq = self.seq.wrapping_add(1);
+        Ok(telemetry)
+    }
+
+    /// Emit readings in background thread
+    /// -`period`   - time between readings
+    ///
+    /// -`return`   - emitter handle, emitter is stopped, when it is dropped
+    pub fn spawn(mut self, period: Duration) -> EmitterHandle {
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let flag: Arc<AtomicBool> = stop.clone();
+        let thread: JoinHandle<()> = thread::spawn(move || {
+            while !flag.load(Ordering::Acquire) {
+                /* lost reading is not resent, next one replaces it */
+                let _ = self.emit();
+                thread::park_timeout(period);
+            }
+        });
+        EmitterHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Running emitter
+///
+/// -`stop`     - stop flag
+/// -`thread`   - emitting thread
+pub struct EmitterHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EmitterHandle {
+    /// Stop emitting
+    pub fn shutdown(mut self) {
+        self.stop_thread();
+    }
+
+    fn stop_thread(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for EmitterHandle {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// Receiver counters
+///
+/// -`received` - accepted datagrams
+/// -`lost`     - sequence numbers, that never came before a later one
+/// -`dropped`  - malformed, duplicated and late datagrams
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TelemetryStats {
+    pub received: u64,
+    pub lost: u64,
+    pub dropped: u64,
+}
+
+/// Receiver of thermometer readings
+///
+/// -`socket`   - receiving socket, non blocking
+/// -`stale`    - reading age, after which device is taken as silent
+/// -`last`     - latest reading and time, when it came
+/// -`stats`    - receiver counters
+pub struct TelemetryReceiver {
+    socket: UdpSocket,
+    stale: Duration,
+    last: Option<(Telemetry, Instant)>,
+    stats: TelemetryStats,
+}
+
+impl TelemetryReceiver {
+    /// Receiver ctor
+    ///
+    /// -`addr`     - listen address, port 0 picks free port
+    /// -`stale`    - reading age, after which it is not used
+    ///
+    /// -`return`   - bound receiver, or socket error
+    pub fn bind<A: ToSocketAddrs>(addr: A, stale: Duration) -> io::Result<Self> {
+        let socket: UdpSocket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(TelemetryReceiver {
+            socket,
+            stale,
+            last: None,
+            stats: TelemetryStats::default(),
+        })
+    }
+
+    /// Listen address
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Receiver counters
+    pub fn stats(&self) -> TelemetryStats {
+        self.stats
+    }
+
+    /// Take all pending datagrams, keep the latest reading
+    /// -`return`   - socket error
+    pub fn poll(&mut self) -> io::Result<()> {
+        let mut buf: [u8; DATAGRAM_LEN + 1] = [0; DATAGRAM_LEN + 1];
+        loop {
+            let len: usize = match self.socket.recv_from(&mut buf) {
+                Ok((len, _)) => len,
+                Err(err) if io::ErrorKind::WouldBlock == err.kind() => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            match Telemetry::decode(&buf[..len]) {
+                Some(telemetry) => self.accept(telemetry, Instant::now()),
+                None => self.stats.dropped += 1,
+            }
+        }
+    }
+
+    /// Take reading, if it is newer than the latest one
+    fn accept(&mut self, telemetry: Telemetry, now: Instant) {
+        match self.last {
+            /* stale reading is replaced by any, emitter may be restarted */
+            Some((last, time)) if now.duration_since(time) < self.stale => {
+                if !telemetry.is_after(&last) {
+                    self.stats.dropped += 1;
+                    return;
+                }
+                self.stats.lost += u64::from(telemetry.seq.wrapping_sub(last.seq) - 1);
+            }
+            _ => {}
+        }
+        self.stats.received += 1;
+        self.last = Some((telemetry, now));
+    }
+
+    /// Latest reading, that is not stale
+    /// -`return`   - reading, or `Offline` if nothing came, `Timeout` if it is stale
+    pub fn latest(&self) -> Result<Telemetry, BackendError> {
+        match self.last {
+            None => Err(BackendError::Offline),
+            Some((_, time)) if time.elapsed() >= self.stale => Err(BackendError::Timeout),
+            Some((telemetry, _)) => Ok(telemetry),
+        }
+    }
+
+    /// Receiving thermometer
+    /// -`thermometer_text`  - thermometer description
+    ///
+    /// -`return`       - thermometer, updated from this receiver
+    pub fn into_thermometer(self, thermometer_text: &str) -> SmartThermometer {
+        SmartThermometer::with_backend(thermometer_text, crate::backend::shared(self))
+    }
+}
+
+impl DeviceBackend for TelemetryReceiver {
+    fn temperature(&mut self) -> Result<Temperature, BackendError> {
+        self.poll()
+            .map_err(|err| BackendError::Transport(err.to_string()))?;
+        self.latest().map(|telemetry| telemetry.temp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{self, BackendError, SimBackend, TempCurve};
+    use crate::smartthermometer::SmartThermometer;
+    use crate::udp::{self, Telemetry, TelemetryEmitter, TelemetryReceiver, TelemetryStats};
+    use crate::units::Temperature;
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn reading(seq: u32, celsius: f64) -> Telemetry {
+        Telemetry {
+            seq,
+            temp: Temperature::from_celsius(celsius).unwrap(),
+        }
+    }
+
+    #[test]
+    fn telemetry_tests() {
+        let telemetry: Telemetry = reading(u32::MAX, -40.0);
+        let datagram: [u8; udp::DATAGRAM_LEN] = telemetry.encode();
+        assert_eq!(Some(telemetry), Telemetry::decode(&datagram));
+        assert_eq!(None, Telemetry::decode(&datagram[1..]));
+        let mut other: [u8; udp::DATAGRAM_LEN] = datagram;
+        other[0] = udp::VERSION + 1;
+        assert_eq!(None, Telemetry::decode(&other));
+        /* below absolute zero */
+        other = reading(0, 0.0).encode();
+        other[5] = 0xff;
+        assert_eq!(None, Telemetry::decode(&other));
+
+        /* sequence wraps */
+        assert!(reading(0, 0.0).is_after(&telemetry));
+        assert!(!telemetry.is_after(&reading(0, 0.0)));
+        assert!(!telemetry.is_after(&telemetry));
+    }
+
+    #[test]
+    fn udp_receiver_tests() {
+        let stale: Duration = Duration::from_millis(200);
+        let mut receiver: TelemetryReceiver =
+            TelemetryReceiver::bind("127.0.0.1:0", stale).unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let sender: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send = |telemetry: Telemetry| {
+            sender.send_to(&telemetry.encode(), addr).unwrap();
+        };
+
+        assert_eq!(Err(BackendError::Offline), receiver.latest());
+        /* 2 and 3 are lost, 1 comes late, 4 is duplicated */
+        send(reading(0, 20.0));
+        send(reading(4, 24.0));
+        send(reading(1, 21.0));
+        send(reading(4, 24.0));
+        sender.send_to(b"garbage", addr).unwrap();
+        receiver.poll().unwrap();
+        assert_eq!(Ok(reading(4, 24.0)), receiver.latest());
+        assert_eq!(
+            TelemetryStats {
+                received: 2,
+                lost: 3,
+                dropped: 3
+            },
+            receiver.stats()
+        );
+
+        let mut thermometer: SmartThermometer = receiver.into_thermometer("balcony");
+        send(reading(5, 25.0));
+        thermometer.update();
+        assert_eq!(Temperature::from_celsius(25.0).unwrap(), thermometer.temp);
+        /* silent thermometer keeps the last reading */
+        thread::sleep(stale);
+        assert_eq!(Err(BackendError::Timeout), thermometer.try_update());
+        assert_eq!(Temperature::from_celsius(25.0).unwrap(), thermometer.temp);
+        /* restarted emitter is taken after staleness */
+        send(reading(0, 19.0));
+        assert_eq!(Ok(()), thermometer.try_update());
+        assert_eq!(Temperature::from_celsius(19.0).unwrap(), thermometer.temp);
+    }
+
+    #[test]
+    fn udp_emitter_tests() {
+        let mut receiver: TelemetryReceiver =
+            TelemetryReceiver::bind("127.0.0.1:0", Duration::from_secs(5)).unwrap();
+        let sim: SimBackend = SimBackend::new(1).with_temp(TempCurve::Cycle(vec![
+            Temperature::from_celsius(18.0).unwrap(),
+            Temperature::from_celsius(19.0).unwrap(),
+        ]));
+        let source: SmartThermometer = SmartThermometer::with_backend("hall", backend::shared(sim));
+        let mut emitter: TelemetryEmitter =
+            TelemetryEmitter::bind("127.0.0.1:0", receiver.local_addr().unwrap(), source).unwrap();
+        assert_eq!(reading(0, 18.0), emitter.emit().unwrap());
+        assert_eq!(reading(1, 19.0), emitter.emit().unwrap());
+        receiver.poll().unwrap();
+        assert_eq!(Ok(reading(1, 19.0)), receiver.latest());
+
+        let addr = receiver.local_addr().unwrap();
+        let mut thermometer: SmartThermometer = receiver.into_thermometer("remote hall");
+        let handle = TelemetryEmitter::bind("127.0.0.1:0", addr, SmartThermometer::new("kitchen"))
+            .unwrap()
+            .spawn(Duration::from_millis(10));
+        /* new emitter starts from 0, it is taken, when it passes the old sequence */
+        let warm: Temperature = Temperature::from_celsius(20.0).unwrap();
+        let start: Instant = Instant::now();
+        while warm != thermometer.temp {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(5));
+            thermometer.update();
+        }
+        handle.shutdown();
+    }
+}
@@ -0,0 +1,495 @@
+//! Physical units of device readings
+//! values are integers in the smallest unit,
+//! so arithmetic is exact and checked
+use std::fmt;
+use std::ops::Neg;
+use std::time::Duration;
+
+/// Temperature unit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TempUnit {
+    #[default]
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    /// Unit symbol
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Kelvin => "K",
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+/// Celsius zero in mK
+const CELSIUS_ZERO_MK: i64 = 273_150;
+
+/// Absolute temperature, never below absolute zero
+///
+/// -`mk`   - temperature in milli Kelvin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "i64", into = "i64")
+)]
+pub struct Temperature {
+    mk: i64,
+}
+
+/// Temperature difference, may be negative
+///
+/// -`mk`   - difference in milli Kelvin
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempDelta {
+    mk: i64,
+}
+
+impl Temperature {
+    /// Absolute zero
+    pub const ABSOLUTE_ZERO: Self = Temperature { mk: 0 };
+    /// Water freezing point, 0 °C
+    pub const ZERO_CELSIUS: Self = Temperature {
+        mk: CELSIUS_ZERO_MK,
+    };
+
+    /// Temperature ctor
+    /// -`mk`       - temperature in milli Kelvin
+    ///
+    /// -`return`   - temperature, or None, if it is below absolute zero
+    pub const fn from_millikelvin(mk: i64) -> Option<Self> {
+        if mk < 0 {
+            None
+        } else {
+            Some(Temperature { mk })
+        }
+    }
+
+    /// Temperature ctor, value is rounded to mK
+    /// -`value`    - temperature in the unit
+    /// -`unit`     - unit of value
+    ///
+    /// -`return`   - temperature, or None, if it is below absolute zero or not finite
+    pub fn from_unit(value: f64, unit: TempUnit) -> Option<Self> {
+        let mk: f64 = match unit {
+            TempUnit::Kelvin => value * 1000.0,
+            TempUnit::Celsius => value * 1000.0 + CELSIUS_ZERO_MK as f64,
+            TempUnit::Fahrenheit => (value - 32.0) * 5000.0 / 9.0 + CELSIUS_ZERO_MK as f64,
+        };
+        /* rounding error may not cross absolute zero */
+        if !mk.is_finite() || mk < -0.5 || mk > i64::MAX as f64 {
+            return None;
+        }
+        Self::from_millikelvin(mk.round() as i64)
+    }
+
+    /// Temperature in Kelvin
+    pub fn from_kelvin(kelvin: f64) -> Option<Self> {
+        Self::from_unit(kelvin, TempUnit::Kelvin)
+    }
+
+    /// Temperature in degrees Celsius
+    pub fn from_celsius(celsius: f64) -> Option<Self> {
+        Self::from_unit(celsius, TempUnit::Celsius)
+    }
+
+    /// Temperature in degrees Fahrenheit
+    pub fn from_fahrenheit(fahrenheit: f64) -> Option<Self> {
+        Self::from_unit(fahrenheit, TempUnit::Fahrenheit)
+    }
+
+    /// Temperature in milli Kelvin
+    pub const fn millikelvin(self) -> i64 {
+        self.mk
+    }
+
+    /// Temperature value in the unit
+    pub fn to_unit(self, unit: TempUnit) -> f64 {
+        match unit {
+            TempUnit::Kelvin => self.mk as f64 / 1000.0,
+            TempUnit::Celsius => (self.mk - CELSIUS_ZERO_MK) as f64 / 1000.0,
+            TempUnit::Fahrenheit => (self.mk - CELSIUS_ZERO_MK) as f64 * 9.0 / 5000.0 + 32.0,
+        }
+    }
+
+    /// Temperature in Kelvin
+    pub fn kelvin(self) -> f64 {
+        self.to_unit(TempUnit::Kelvin)
+    }
+
+    /// Temperature in degrees Celsius
+    pub fn celsius(self) -> f64 {
+        self.to_unit(TempUnit::Celsius)
+    }
+
+    /// Temperature in degrees Fahrenheit
+    pub fn fahrenheit(self) -> f64 {
+        self.to_unit(TempUnit::Fahrenheit)
+    }
+
+    /// Add difference
+    /// -`return`   - temperature, or None, if result is below absolute zero or overflows
+    pub fn checked_add(self, delta: TempDelta) -> Option<Self> {
+        self.mk
+            .checked_add(delta.mk)
+            .and_then(Self::from_millikelvin)
+    }
+
+    /// Subtract difference
+    /// -`return`   - temperature, or None, if result is below absolute zero or overflows
+    pub fn checked_sub(self, delta: TempDelta) -> Option<Self> {
+        self.mk
+            .checked_sub(delta.mk)
+            .and_then(Self::from_millikelvin)
+    }
+
+    /// Difference between temperatures
+    /// -`return`   - self minus other
+    pub fn delta(self, other: Self) -> TempDelta {
+        /* both are not negative, so it never overflows */
+        TempDelta {
+            mk: self.mk - other.mk,
+        }
+    }
+
+    /// Format in the unit
+    /// -`unit`     - display unit
+    ///
+    /// -`return`   - displayable temperature, precision of format is respected
+    pub fn display(self, unit: TempUnit) -> TempDisplay {
+        TempDisplay { temp: self, unit }
+    }
+}
+
+impl fmt::Display for Temperature {
+    /// Temperature in Kelvin
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.display(TempUnit::Kelvin).fmt(fmt)
+    }
+}
+
+impl TryFrom<i64> for Temperature {
+    type Error = &'static str;
+
+    /// Temperature from milli Kelvin
+    fn try_from(mk: i64) -> Result<Self, Self::Error> {
+        Self::from_millikelvin(mk).ok_or("temperature is below absolute zero")
+    }
+}
+
+impl From<Temperature> for i64 {
+    /// Temperature in milli Kelvin
+    fn from(temp: Temperature) -> Self {
+        temp.mk
+    }
+}
+
+/// Temperature in selected unit
+///
+/// -`temp`     - temperature
+/// -`unit`     - display unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempDisplay {
+    pub temp: Temperature,
+    pub unit: TempUnit,
+}
+
+impl fmt::Display for TempDisplay {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let value: f64 = self.temp.to_unit(self.unit);
+        match fmt.precision() {
+            Some(precision) => write!(fmt, "{:.*} {}", precision, value, self.unit.symbol()),
+            None => write!(fmt, "{} {}", value, self.unit.symbol()),
+        }
+    }
+}
+
+impl TempDelta {
+    /// Difference ctor
+    /// -`mk`       - difference in milli Kelvin
+    pub const fn from_millikelvin(mk: i64) -> Self {
+        TempDelta { mk }
+    }
+
+    /// Difference in Kelvin, same as in degrees Celsius, rounded to mK
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        TempDelta {
+            mk: (kelvin * 1000.0).round() as i64,
+        }
+    }
+
+    /// Difference in degrees Fahrenheit, rounded to mK
+    pub fn from_fahrenheit(fahrenheit: f64) -> Self {
+        TempDelta {
+            mk: (fahrenheit * 5000.0 / 9.0).round() as i64,
+        }
+    }
+
+    /// Difference in milli Kelvin
+    pub const fn millikelvin(self) -> i64 {
+        self.mk
+    }
+
+    /// Difference in Kelvin
+    pub fn kelvin(self) -> f64 {
+        self.mk as f64 / 1000.0
+    }
+}
+
+impl Neg for TempDelta {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        TempDelta { mk: -self.mk }
+    }
+}
+
+/// Power, never negative
+///
+/// -`mw`   - power in milli Watt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Power {
+    mw: u64,
+}
+
+/// Energy, never negative
+///
+/// -`uj`   - energy in micro Joule, that is mW * ms
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Energy {
+    uj: u64,
+}
+
+/// Convert not negative float value into integer units, rounded
+fn to_units(value: f64, scale: f64) -> Option<u64> {
+    let units: f64 = value * scale;
+    if !units.is_finite() || units < -0.5 || units >= u64::MAX as f64 {
+        return None;
+    }
+    Some(units.round() as u64)
+}
+
+impl Power {
+    /// No power
+    pub const ZERO: Self = Power { mw: 0 };
+
+    /// Power ctor
+    /// -`mw`       - power in milli Watt
+    pub const fn from_milliwatts(mw: u64) -> Self {
+        Power { mw }
+    }
+
+    /// Power in Watt, rounded to mW
+    /// -`return`   - power, or None, if it is negative or not finite
+    pub fn from_watts(watts: f64) -> Option<Self> {
+        to_units(watts, 1e3).map(Self::from_milliwatts)
+    }
+
+    /// Power in kilo Watt, rounded to mW
+    /// -`return`   - power, or None, if it is negative or not finite
+    pub fn from_kilowatts(kilowatts: f64) -> Option<Self> {
+        to_units(kilowatts, 1e6).map(Self::from_milliwatts)
+    }
+
+    /// Power in milli Watt
+    pub const fn milliwatts(self) -> u64 {
+        self.mw
+    }
+
+    /// Power in Watt
+    pub fn watts(self) -> f64 {
+        self.mw as f64 / 1e3
+    }
+
+    /// Power in kilo Watt
+    pub fn kilowatts(self) -> f64 {
+        self.mw as f64 / 1e6
+    }
+
+    /// Energy consumed with this power
+    /// -`time`     - consumption time
+    ///
+    /// -`return`   - energy, saturated on overflow
+    pub fn energy(self, time: Duration) -> Energy {
+        /* mW * us is nJ */
+        let nj: u128 = self.mw as u128 * time.as_micros();
+        Energy {
+            uj: u64::try_from(nj / 1000).unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Add power
+    /// -`return`   - power, or None on overflow
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.mw.checked_add(other.mw).map(Self::from_milliwatts)
+    }
+
+    /// Subtract power
+    /// -`return`   - power, or None, if result is negative
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.mw.checked_sub(other.mw).map(Self::from_milliwatts)
+    }
+}
+
+impl fmt::Display for Power {
+    /// Power in milli Watt
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} mW", self.mw)
+    }
+}
+
+/// Micro Joule in one Watt hour
+const UJ_PER_WH: f64 = 3.6e9;
+
+impl Energy {
+    /// No energy
+    pub const ZERO: Self = Energy { uj: 0 };
+
+    /// Energy ctor
+    /// -`uj`       - energy in micro Joule
+    pub const fn from_microjoules(uj: u64) -> Self {
+        Energy { uj }
+    }
+
+    /// Energy in Watt hours, rounded to uJ
+    /// -`return`   - energy, or None, if it is negative or not finite
+    pub fn from_watt_hours(wh: f64) -> Option<Self> {
+        to_units(wh, UJ_PER_WH).map(Self::from_microjoules)
+    }
+
+    /// Energy in kilo Watt hours, rounded to uJ
+    /// -`return`   - energy, or None, if it is negative or not finite
+    pub fn from_kilowatt_hours(kwh: f64) -> Option<Self> {
+        to_units(kwh, UJ_PER_WH * 1e3).map(Self::from_microjoules)
+    }
+
+    /// Energy in micro Joule
+    pub const fn microjoules(self) -> u64 {
+        self.uj
+    }
+
+    /// Energy in Watt hours
+    pub fn watt_hours(self) -> f64 {
+        self.uj as f64 / UJ_PER_WH
+    }
+
+    /// Energy in kilo Watt hours
+    pub fn kilowatt_hours(self) -> f64 {
+        self.uj as f64 / (UJ_PER_WH * 1e3)
+    }
+
+    /// Add energy
+    /// -`return`   - energy, or None on overflow
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.uj.checked_add(other.uj).map(Self::from_microjoules)
+    }
+
+    /// Add energy, counter stops at max
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self::from_microjoules(self.uj.saturating_add(other.uj))
+    }
+
+    /// Subtract energy
+    /// -`return`   - energy, or None, if result is negative
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.uj.checked_sub(other.uj).map(Self::from_microjoules)
+    }
+}
+
+impl fmt::Display for Energy {
+    /// Energy in Watt hours, precision of format is respected
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match fmt.precision() {
+            Some(precision) => write!(fmt, "{:.*} Wh", precision, self.watt_hours()),
+            None => write!(fmt, "{} Wh", self.watt_hours()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::{Energy, Power, TempDelta, TempUnit, Temperature};
+    use std::time::Duration;
+
+    #[test]
+    fn temperature_tests() {
+        let room: Temperature = Temperature::from_celsius(20.5).unwrap();
+        assert_eq!(293_650, room.millikelvin());
+        assert_eq!(20.5, room.celsius());
+        assert_eq!(68.9, room.fahrenheit());
+        assert_eq!(Some(room), Temperature::from_fahrenheit(68.9));
+        assert_eq!(
+            Some(Temperature::ABSOLUTE_ZERO),
+            Temperature::from_celsius(-273.15)
+        );
+        assert_eq!(None, Temperature::from_kelvin(-0.001));
+        assert_eq!(None, Temperature::from_celsius(f64::NAN));
+
+        /* offsets may be negative, result may not */
+        let cold: Temperature = room.checked_add(TempDelta::from_kelvin(-25.0)).unwrap();
+        assert_eq!(-4.5, cold.celsius());
+        assert_eq!(TempDelta::from_fahrenheit(-45.0), cold.delta(room));
+        assert_eq!(Some(room), cold.checked_sub(-TempDelta::from_kelvin(25.0)));
+        assert_eq!(None, cold.checked_sub(TempDelta::from_kelvin(300.0)));
+        assert_eq!(
+            None,
+            room.checked_add(TempDelta::from_millikelvin(i64::MAX))
+        );
+        assert!(cold < room);
+
+        assert_eq!("293.65 K", room.to_string());
+        assert_eq!("20.5 °C", room.display(TempUnit::Celsius).to_string());
+        assert_eq!("-4 °C", format!("{:.0}", cold.display(TempUnit::Celsius)));
+        assert_eq!(
+            "68.90 °F",
+            format!("{:.2}", room.display(TempUnit::Fahrenheit))
+        );
+        assert!(Temperature::try_from(-1).is_err());
+    }
+
+    #[test]
+    fn power_tests() {
+        let kettle: Power = Power::from_kilowatts(2.2).unwrap();
+        assert_eq!(2_200_000, kettle.milliwatts());
+        assert_eq!(2200.0, kettle.watts());
+        assert_eq!(Some(kettle), Power::from_watts(2200.0));
+        assert_eq!(None, Power::from_watts(-1.0));
+        assert_eq!(
+            None,
+            kettle.checked_sub(Power::from_kilowatts(3.0).unwrap())
+        );
+        assert_eq!("2200000 mW", kettle.to_string());
+
+        /* half an hour of 2.2 kW is 1.1 kWh */
+        let energy: Energy = kettle.energy(Duration::from_secs(1800));
+        assert_eq!(Energy::from_kilowatt_hours(1.1), Some(energy));
+        assert_eq!(1100.0, energy.watt_hours());
+        assert_eq!("1100.00 Wh", format!("{:.2}", energy));
+        assert_eq!(
+            Energy::from_microjoules(3),
+            Power::from_milliwatts(3).energy(Duration::from_millis(1))
+        );
+        assert_eq!(
+            Energy::from_microjoules(u64::MAX),
+            Power::from_milliwatts(u64::MAX).energy(Duration::from_secs(2))
+        );
+        assert_eq!(None, energy.checked_add(Energy::from_microjoules(u64::MAX)));
+        assert_eq!(Energy::ZERO, energy.checked_sub(energy).unwrap());
+    }
+}
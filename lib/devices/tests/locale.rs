@@ -0,0 +1,47 @@
+//! Runtime locale selection
+//! current locale is process wide,
+//! so it is switched by one test only
+use devices::locale::{self, Catalog, MsgKey};
+use devices::smartsocket::SmartSocket;
+use devices::{SmartDevice, SmartDeviceAccess};
+use std::path::PathBuf;
+
+#[test]
+fn locale_select_tests() {
+    let dev: SmartDevice = SmartDevice::from(SmartSocket::new("socket0"));
+    assert_eq!("en", locale::current().locale());
+    assert_eq!("name: socket0\nstatus: off\nenergy: 0.000 Wh", dev.status());
+
+    /* shipped catalog files are loadable */
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "locales", "ru.lang"]
+        .iter()
+        .collect();
+    let ru: Catalog = Catalog::load(&path).unwrap();
+    assert_eq!(Catalog::builtin("ru").unwrap(), ru);
+    locale::set_locale(ru);
+    assert_eq!(
+        "имя: socket0\nсостояние: выкл\nэнергия: 0.000 Вт·ч",
+        dev.status()
+    );
+    assert_eq!(
+        "Имя = socket0\tСостояние = Выкл\tМощность = 0 мВт",
+        dev.downcast_ref::<SmartSocket>().unwrap().to_string()
+    );
+
+    /* partial catalog file */
+    let path: PathBuf = std::env::temp_dir().join(format!("de-{}.lang", std::process::id()));
+    std::fs::write(&path, "device.off = aus\n").unwrap();
+    let de: Catalog = Catalog::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    locale::set_locale(de);
+    assert_eq!("name: socket0\nstatus: aus\nenergy: 0.000 Wh", dev.status());
+    assert_eq!("room", locale::tr(MsgKey::Room));
+
+    assert!(locale::select_locale("xx").is_err());
+    assert!(Catalog::load("no such catalog.lang").is_err());
+    locale::select_locale("en").unwrap();
+    assert_eq!(
+        "Name = socket0\tState = Off\tPower = 0 mW",
+        dev.downcast_ref::<SmartSocket>().unwrap().to_string()
+    );
+}
@@ -0,0 +1,75 @@
+//! LRU cache against naive `Vec` cache,
+//! `Vec` keeps entries from most to least recently used,
+//! so every touch is a linear search and a shift
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use dll::lru::LruCache;
+
+/// Naive cache, head is the most recently used entry
+struct VecLru<K, V> {
+    entries: Vec<(K, V)>,
+    cap: usize,
+}
+
+impl<K: PartialEq, V> VecLru<K, V> {
+    fn new(cap: usize) -> Self {
+        VecLru {
+            entries: Vec::with_capacity(cap + 1),
+            cap,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let at: usize = self.entries.iter().position(|(sel, _)| sel == key)?;
+        let entry: (K, V) = self.entries.remove(at);
+        self.entries.insert(0, entry);
+        Some(&self.entries[0].1)
+    }
+
+    fn put(&mut self, key: K, value: V) {
+        if let Some(at) = self.entries.iter().position(|(sel, _)| *sel == key) {
+            self.entries.remove(at);
+        }
+        self.entries.insert(0, (key, value));
+        self.entries.truncate(self.cap);
+    }
+}
+
+/// Keys with some locality, a quarter of them miss
+fn keys(cap: usize) -> Vec<u64> {
+    (0..4096u64)
+        .map(|sel| (sel.wrapping_mul(2654435761) >> 7) % (cap as u64 * 5 / 4))
+        .collect()
+}
+
+fn lru_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lru");
+    for cap in [16usize, 256, 4096] {
+        let keys: Vec<u64> = keys(cap);
+        group.bench_with_input(BenchmarkId::new("dll", cap), &keys, |b, keys| {
+            let mut cache: LruCache<u64, u64> = LruCache::new(cap);
+            b.iter(|| {
+                for &key in keys {
+                    if cache.get(&key).is_none() {
+                        cache.put(key, key);
+                    }
+                }
+                black_box(cache.len())
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("vec", cap), &keys, |b, keys| {
+            let mut cache: VecLru<u64, u64> = VecLru::new(cap);
+            b.iter(|| {
+                for &key in keys {
+                    if cache.get(&key).is_none() {
+                        cache.put(key, key);
+                    }
+                }
+                black_box(cache.entries.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lru_bench);
+criterion_main!(benches);
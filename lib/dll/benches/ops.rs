@@ -0,0 +1,268 @@
+//! Intrusive chain and owning list against std containers
+//! head/tail insertion, unlink from the middle, full iteration
+//! and find by predicate, at 10 to 1M elements.
+//!
+//! Results are per element, so regressions of `addh`, `addt`
+//! and `unlink` are visible at every size. Save a baseline
+//! before the change and compare against it after:
+//! `cargo bench -p dll --bench ops -- --save-baseline main`
+//! `cargo bench -p dll --bench ops -- --baseline main`
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
+};
+use dll::list::DllList;
+use dll::Dll;
+use std::collections::{LinkedList, VecDeque};
+use std::pin::Pin;
+
+/// Container sizes
+const SIZES: [usize; 4] = [10, 1_000, 100_000, 1_000_000];
+
+/// Intrusive chain, sentinel and pinned nodes
+///
+/// -`nurse`  - chain sentinel
+/// -`nodes`  - chain nodes, in order of creation
+struct Chain {
+    nurse: Pin<Box<Dll<u64>>>,
+    nodes: Vec<Pin<Box<Dll<u64>>>>,
+}
+
+impl Chain {
+    /// Chain with unlinked nodes
+    fn unlinked(len: usize) -> Self {
+        Chain {
+            nurse: Box::pin(Dll::from(u64::MAX)),
+            nodes: (0..len as u64)
+                .map(|data| Box::pin(Dll::from(data)))
+                .collect(),
+        }
+    }
+
+    /// Chain with all nodes linked in order of creation
+    fn linked(len: usize) -> Self {
+        let mut chain: Chain = Chain::unlinked(len);
+        for node in chain.nodes.iter_mut() {
+            chain.nurse.as_mut().addt(node.as_mut());
+        }
+        chain
+    }
+}
+
+/// Group with few samples, big containers take long to build
+fn group<'a>(c: &'a mut Criterion, name: &str) -> BenchmarkGroup<'a, WallTime> {
+    let mut group: BenchmarkGroup<'a, WallTime> = c.benchmark_group(name);
+    group.sample_size(10);
+    group
+}
+
+fn insert_bench(c: &mut Criterion) {
+    let mut group = group(c, "insert");
+    for len in SIZES {
+        group.throughput(Throughput::Elements(len as u64));
+        /* nodes are allocated outside of the measurement */
+        group.bench_function(BenchmarkId::new("dll_addh", len), |b| {
+            b.iter_batched(
+                || Chain::unlinked(len),
+                |mut chain: Chain| {
+                    for node in chain.nodes.iter_mut() {
+                        chain.nurse.as_mut().addh(node.as_mut());
+                    }
+                    chain
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("dll_addt", len), |b| {
+            b.iter_batched(
+                || Chain::unlinked(len),
+                |mut chain: Chain| {
+                    for node in chain.nodes.iter_mut() {
+                        chain.nurse.as_mut().addt(node.as_mut());
+                    }
+                    chain
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("dll_list_push_front", len), |b| {
+            b.iter_batched(
+                DllList::new,
+                |mut list: DllList<u64>| {
+                    for data in 0..len as u64 {
+                        list.push_front(data);
+                    }
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("dll_list_push_back", len), |b| {
+            b.iter_batched(
+                DllList::new,
+                |mut list: DllList<u64>| {
+                    for data in 0..len as u64 {
+                        list.push_back(data);
+                    }
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked_list_push_front", len), |b| {
+            b.iter_batched(
+                LinkedList::new,
+                |mut list: LinkedList<u64>| {
+                    for data in 0..len as u64 {
+                        list.push_front(data);
+                    }
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("linked_list_push_back", len), |b| {
+            b.iter_batched(
+                LinkedList::new,
+                |mut list: LinkedList<u64>| {
+                    for data in 0..len as u64 {
+                        list.push_back(data);
+                    }
+                    list
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("vec_deque_push_front", len), |b| {
+            b.iter_batched(
+                VecDeque::new,
+                |mut deque: VecDeque<u64>| {
+                    for data in 0..len as u64 {
+                        deque.push_front(data);
+                    }
+                    deque
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("vec_deque_push_back", len), |b| {
+            b.iter_batched(
+                VecDeque::new,
+                |mut deque: VecDeque<u64>| {
+                    for data in 0..len as u64 {
+                        deque.push_back(data);
+                    }
+                    deque
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn unlink_bench(c: &mut Criterion) {
+    /* middle element is taken out and put back,
+     * so every iteration sees the same container
+     */
+    let mut group = group(c, "unlink_middle");
+    for len in SIZES {
+        let mid: usize = len / 2;
+        group.bench_function(BenchmarkId::new("dll", len), |b| {
+            let mut chain: Chain = Chain::linked(len);
+            let (head, tail) = chain.nodes.split_at_mut(mid);
+            let prev: &mut Pin<Box<Dll<u64>>> = &mut head[mid - 1];
+            let node: &mut Pin<Box<Dll<u64>>> = &mut tail[0];
+            b.iter(|| {
+                node.as_mut().unlink();
+                prev.as_mut().addh(node.as_mut());
+            })
+        });
+        group.bench_function(BenchmarkId::new("dll_list", len), |b| {
+            let mut list: DllList<u64> = (0..len as u64).collect();
+            b.iter(|| {
+                let data: u64 = list.remove(mid).unwrap();
+                let mut cursor = list.cursor_front_mut();
+                for _ in 0..mid {
+                    cursor.move_next();
+                }
+                cursor.insert_before(black_box(data));
+            })
+        });
+        group.bench_function(BenchmarkId::new("linked_list", len), |b| {
+            let mut list: LinkedList<u64> = (0..len as u64).collect();
+            b.iter(|| {
+                let mut tail: LinkedList<u64> = list.split_off(mid);
+                let data: u64 = tail.pop_front().unwrap();
+                tail.push_front(black_box(data));
+                list.append(&mut tail);
+            })
+        });
+        group.bench_function(BenchmarkId::new("vec_deque", len), |b| {
+            let mut deque: VecDeque<u64> = (0..len as u64).collect();
+            b.iter(|| {
+                let data: u64 = deque.remove(mid).unwrap();
+                deque.insert(mid, black_box(data));
+            })
+        });
+    }
+    group.finish();
+}
+
+fn iter_bench(c: &mut Criterion) {
+    let mut group = group(c, "iter");
+    for len in SIZES {
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_function(BenchmarkId::new("dll", len), |b| {
+            let chain: Chain = Chain::linked(len);
+            b.iter(|| unsafe { chain.nurse.iter() }.sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("dll_list", len), |b| {
+            let list: DllList<u64> = (0..len as u64).collect();
+            b.iter(|| list.iter().sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("linked_list", len), |b| {
+            let list: LinkedList<u64> = (0..len as u64).collect();
+            b.iter(|| list.iter().sum::<u64>())
+        });
+        group.bench_function(BenchmarkId::new("vec_deque", len), |b| {
+            let deque: VecDeque<u64> = (0..len as u64).collect();
+            b.iter(|| deque.iter().sum::<u64>())
+        });
+    }
+    group.finish();
+}
+
+fn find_bench(c: &mut Criterion) {
+    /* looked up element is the last one, whole container is walked */
+    let mut group = group(c, "find");
+    for len in SIZES {
+        let last: u64 = len as u64 - 1;
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_function(BenchmarkId::new("dll", len), |b| {
+            let chain: Chain = Chain::linked(len);
+            b.iter(|| {
+                unsafe { chain.nurse.iter() }
+                    .find(|&&data| black_box(last) == data)
+                    .copied()
+            })
+        });
+        group.bench_function(BenchmarkId::new("dll_list", len), |b| {
+            let list: DllList<u64> = (0..len as u64).collect();
+            b.iter(|| list.find(|&data| black_box(last) == data).copied())
+        });
+        group.bench_function(BenchmarkId::new("linked_list", len), |b| {
+            let list: LinkedList<u64> = (0..len as u64).collect();
+            b.iter(|| list.iter().find(|&&data| black_box(last) == data).copied())
+        });
+        group.bench_function(BenchmarkId::new("vec_deque", len), |b| {
+            let deque: VecDeque<u64> = (0..len as u64).collect();
+            b.iter(|| deque.iter().find(|&&data| black_box(last) == data).copied())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert_bench, unlink_bench, iter_bench, find_bench);
+criterion_main!(benches);
@@ -0,0 +1,677 @@
+//! Intrusive links embedded as fields
+//! value keeps one `ListLink` field per list it can be
+//! a member of, so a single value can be in several lists
+//! at once without copying. Adapter knows the offset of
+//! the field and maps link back to its value (`container_of`).
+//!
+//! Like `Dll`, value is linked only while pinned, and its
+//! links are unlinked, when the value is dropped.
+//!
+//! `LinkList` does not borrow its members, so it is walked
+//! only unsafely. `LinkChain` borrows every member for its
+//! lifetime, shared, so one value is in several chains at once
+//! and every chain is iterated safely.
+//! ```
+//! use core::pin::pin;
+//! use dll::adapter::{LinkChain, ListLink};
+//!
+//! struct Device {
+//!     name: &'static str,
+//!     room: ListLink,
+//!     powered: ListLink,
+//! }
+//! dll::dll_adapter!(RoomAdapter = Device { room });
+//! dll::dll_adapter!(PoweredAdapter = Device { powered });
+//!
+//! let socket = pin!(Device { name: "socket", room: ListLink::new(), powered: ListLink::new() });
+//! let socket = socket.into_ref();
+//! let mut room = pin!(LinkChain::<RoomAdapter>::new());
+//! let mut powered = pin!(LinkChain::<PoweredAdapter>::new());
+//! room.as_mut().push_back(socket);
+//! powered.as_mut().push_back(socket);
+//! assert!(powered.as_mut().remove(&socket));
+//! assert_eq!(vec!["socket"], room.iter().map(|dev| dev.name).collect::<Vec<_>>());
+//! assert!(powered.is_empty());
+//! ```
+use crate::Link;
+use core::cell::UnsafeCell;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::pin::Pin;
+
+/// Links field of a value, that can be a member of one list
+///
+/// -`link`  - next and prev links, null if unlinked,
+///            shared members are linked through it too
+#[repr(C)]
+pub struct ListLink {
+    link: UnsafeCell<Link>,
+}
+
+impl ListLink {
+    /// Links ctor
+    /// -`return`  - new unlinked links
+    pub const fn new() -> Self {
+        ListLink {
+            link: UnsafeCell::new(Link::new()),
+        }
+    }
+
+    /// Return true, if links are a member of some list
+    pub fn is_linked(&self) -> bool {
+        unsafe { !Link::next(self.as_ptr()).is_null() }
+    }
+
+    /// Links, writable while the value is borrowed
+    fn as_ptr(&self) -> *mut Link {
+        self.link.get()
+    }
+}
+
+impl Default for ListLink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ListLink {
+    fn drop(&mut self) {
+        /* keep the list linked */
+        unsafe {
+            Link::detach(self.as_ptr());
+        }
+    }
+}
+
+/// Map between value and one of its `ListLink` fields,
+/// use `dll_adapter!` to implement it
+///
+/// # Safety
+/// `OFFSET` must be the offset of a `ListLink` field in `Value`.
+///
+/// `Value` must not be `Unpin`: linked value is reached through
+/// raw links of its neighbours, so it must never move, while
+/// it is a member. `ListLink` makes its owner `!Unpin`, so
+/// value must not opt out with `impl Unpin`.
+/// `dll_adapter!` checks both at compile time.
+pub unsafe trait Adapter {
+    /// Type of the list members
+    type Value;
+    /// Offset of the links field in the value
+    const OFFSET: usize;
+
+    /// Links field of the value, pointer covers the whole value
+    fn link_of(value: *const Self::Value) -> *const ListLink {
+        value.cast::<u8>().wrapping_add(Self::OFFSET).cast()
+    }
+
+    /// Value, that owns the links field
+    ///
+    /// # Safety
+    /// links must be the adapter field of a `Value`,
+    /// pointer must cover the whole value
+    unsafe fn container_of(link: *const ListLink) -> *const Self::Value {
+        link.cast::<u8>().sub(Self::OFFSET).cast()
+    }
+
+    /// Return true, if value is a member of the adapter list
+    fn is_linked(value: &Self::Value) -> bool {
+        unsafe { (*Self::link_of(value)).is_linked() }
+    }
+
+    /// Take value out of the adapter list, other lists are kept
+    fn unlink(value: Pin<&mut Self::Value>) {
+        unsafe {
+            Link::detach(as_link::<Self>(value));
+        }
+    }
+}
+
+/// Implement `Adapter` for a `ListLink` field of a struct
+/// ```
+/// use dll::adapter::ListLink;
+/// pub struct Node {
+///     data: u32,
+///     link: ListLink,
+/// }
+/// dll::dll_adapter!(pub NodeAdapter = Node { link });
+/// ```
+/// Value, that can be moved out of its pin, is rejected
+/// ```compile_fail
+/// use dll::adapter::ListLink;
+/// pub struct Node {
+///     data: u32,
+///     link: ListLink,
+/// }
+/// impl Unpin for Node {}
+/// dll::dll_adapter!(pub NodeAdapter = Node { link });
+/// ```
+#[macro_export]
+macro_rules! dll_adapter {
+    ($vis:vis $name:ident = $value:ty { $field:ident }) => {
+        /// List adapter
+        $vis struct $name;
+
+        unsafe impl $crate::adapter::Adapter for $name {
+            type Value = $value;
+            const OFFSET: usize = {
+                /* field must be the links */
+                let _: fn(&$value) -> &$crate::adapter::ListLink = |value| &value.$field;
+                ::core::mem::offset_of!($value, $field)
+            };
+        }
+
+        /* value must stay !Unpin: both impls match an Unpin value,
+        so the item lookup below is ambiguous and fails to compile */
+        const _: fn() = || {
+            trait AmbiguousIfUnpin<A> {
+                fn some_item() {}
+            }
+            impl<T: ?Sized> AmbiguousIfUnpin<()> for T {}
+            struct Invalid;
+            impl<T: ?Sized + ::core::marker::Unpin> AmbiguousIfUnpin<Invalid> for T {}
+            let _ = <$value as AmbiguousIfUnpin<_>>::some_item;
+        };
+    };
+}
+
+/// Links of the pinned value, pointer covers the whole value
+unsafe fn as_link<A: Adapter + ?Sized>(value: Pin<&mut A::Value>) -> *mut Link {
+    let value: *mut A::Value = value.get_unchecked_mut();
+    link_ptr::<A>(value)
+}
+
+/// Links of the value, pointer covers the whole value,
+/// so the value is found back from its links,
+/// links are written through `UnsafeCell`, even if value is shared
+fn link_ptr<A: Adapter + ?Sized>(value: *const A::Value) -> *mut Link {
+    A::link_of(value).cast_mut().cast()
+}
+
+/// List of values linked through the adapter field,
+/// list does not own its members
+///
+/// -`nurse`   - list sentinel
+pub struct LinkList<A: Adapter> {
+    nurse: ListLink,
+    marker: PhantomData<*const A::Value>,
+}
+
+impl<A: Adapter> LinkList<A> {
+    /// List ctor
+    /// -`return`  - new empty list, sentinel is linked, when pinned
+    pub const fn new() -> Self {
+        LinkList {
+            nurse: ListLink::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Return true, if list have no members
+    pub fn is_empty(&self) -> bool {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            let next: *mut Link = Link::next(nurse);
+            next.is_null() || nurse == next
+        }
+    }
+
+    /// Members count, list is walked
+    pub fn len(&self) -> usize {
+        unsafe { Link::ring_len(self.nurse.as_ptr()) }
+    }
+
+    /// Add value to head,
+    /// value is unlinked from its previous adapter list first
+    /// -`value`   - value to add
+    pub fn push_front(self: Pin<&mut Self>, value: Pin<&mut A::Value>) {
+        unsafe {
+            let this: *mut Link = Self::nurse(self);
+            let node: *mut Link = as_link::<A>(value);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, this, Link::next(this));
+        }
+    }
+
+    /// Add value to tail,
+    /// value is unlinked from its previous adapter list first
+    /// -`value`   - value to add
+    pub fn push_back(self: Pin<&mut Self>, value: Pin<&mut A::Value>) {
+        unsafe {
+            let this: *mut Link = Self::nurse(self);
+            let node: *mut Link = as_link::<A>(value);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, Link::prev(this), this);
+        }
+    }
+
+    /// Unlink all members, members are not dropped
+    pub fn clear(self: Pin<&mut Self>) {
+        unsafe {
+            let this: *mut Link = Self::nurse(self);
+            unlink_all(this);
+        }
+    }
+
+    /// Iterate over members from head to tail,
+    /// use `LinkChain` to iterate borrowed members safely
+    ///
+    /// # Safety
+    ///
+    /// Members are not borrowed by the iterator,
+    /// so while it is alive they must not be accessed mutably,
+    /// unlinked or dropped through their own handles.
+    pub unsafe fn iter_unchecked(&self) -> Iter<'_, A> {
+        Iter::from_raw(self.nurse.as_ptr())
+    }
+
+    /// Mutably iterate over members from head to tail,
+    /// members stay pinned
+    ///
+    /// # Safety
+    ///
+    /// Members are not borrowed by the iterator,
+    /// so while it is alive they must not be accessed at all,
+    /// unlinked or dropped through their own handles.
+    pub unsafe fn iter_mut_unchecked(self: Pin<&mut Self>) -> IterMut<'_, A> {
+        let nurse: *mut Link = Self::nurse(self);
+        IterMut {
+            head: Link::next(nurse),
+            tail: Link::prev(nurse),
+            len: Link::ring_len(nurse),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sentinel links of the pinned list
+    unsafe fn nurse(this: Pin<&mut Self>) -> *mut Link {
+        this.get_unchecked_mut().nurse.as_ptr()
+    }
+}
+
+/// Unlink every member of the ring
+unsafe fn unlink_all(nurse: *mut Link) {
+    if Link::next(nurse).is_null() {
+        return;
+    }
+    while nurse != Link::next(nurse) {
+        Link::detach(Link::next(nurse));
+    }
+}
+
+impl<A: Adapter> Default for LinkList<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Adapter> Drop for LinkList<A> {
+    /// Members are unlinked, so they can join other lists
+    fn drop(&mut self) {
+        unsafe {
+            unlink_all(self.nurse.as_ptr());
+        }
+    }
+}
+
+/// List of values linked through the adapter field,
+/// that borrows its members
+///
+/// Members are borrowed shared for `'a`, so a value is a member
+/// of several chains with other adapters at once, and it can't
+/// be mutated, unlinked or dropped through its own handles, while
+/// any of them is alive. Members are mutated through interior
+/// mutability. Dropped chain unlinks its members.
+///
+/// -`nurse`   - chain sentinel
+/// -`marker`  - members are borrowed for `'a`
+pub struct LinkChain<'a, A: Adapter> {
+    nurse: ListLink,
+    marker: PhantomData<Pin<&'a A::Value>>,
+}
+
+impl<'a, A: Adapter> LinkChain<'a, A> {
+    /// Chain ctor, chain is pinned before values are added
+    /// -`return`  - new empty chain
+    pub const fn new() -> Self {
+        LinkChain {
+            nurse: ListLink::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Return true, if chain have no members
+    pub fn is_empty(&self) -> bool {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            let next: *mut Link = Link::next(nurse);
+            next.is_null() || nurse == next
+        }
+    }
+
+    /// Members count, chain is walked
+    pub fn len(&self) -> usize {
+        unsafe { Link::ring_len(self.nurse.as_ptr()) }
+    }
+
+    /// Add value to head,
+    /// value is unlinked from its previous adapter list first
+    /// -`value`   - value, borrowed until the chain is dropped
+    pub fn push_front(self: Pin<&mut Self>, value: Pin<&'a A::Value>) {
+        unsafe {
+            let this: *mut Link = self.nurse.as_ptr();
+            let node: *mut Link = link_ptr::<A>(&*value);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, this, Link::next(this));
+        }
+    }
+
+    /// Add value to tail,
+    /// value is unlinked from its previous adapter list first
+    /// -`value`   - value, borrowed until the chain is dropped
+    pub fn push_back(self: Pin<&mut Self>, value: Pin<&'a A::Value>) {
+        unsafe {
+            let this: *mut Link = self.nurse.as_ptr();
+            let node: *mut Link = link_ptr::<A>(&*value);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, Link::prev(this), this);
+        }
+    }
+
+    /// Take value out of the chain, chain is walked
+    /// -`value`   - value to take out
+    /// -`return`  - true, if value was a member of the chain
+    pub fn remove(self: Pin<&mut Self>, value: &A::Value) -> bool {
+        unsafe {
+            let this: *mut Link = self.nurse.as_ptr();
+            let node: *mut Link = link_ptr::<A>(value);
+            let mut sel: *mut Link = Link::next(this);
+            while !sel.is_null() && this != sel {
+                if node == sel {
+                    Link::detach(node);
+                    return true;
+                }
+                sel = Link::next(sel);
+            }
+            false
+        }
+    }
+
+    /// Unlink all members, borrows end, when the chain is dropped
+    pub fn clear(self: Pin<&mut Self>) {
+        unsafe {
+            unlink_all(self.nurse.as_ptr());
+        }
+    }
+
+    /// Iterate over members from head to tail
+    /// -`return`  - iterator over members
+    pub fn iter(&self) -> Iter<'_, A> {
+        /* members are borrowed shared, so nothing can mutate or drop them */
+        unsafe { Iter::from_raw(self.nurse.as_ptr()) }
+    }
+}
+
+impl<'a, A: Adapter> Default for LinkChain<'a, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, A: Adapter> Drop for LinkChain<'a, A> {
+    /// Members are unlinked, so they are free, when borrow ends
+    fn drop(&mut self) {
+        unsafe {
+            unlink_all(self.nurse.as_ptr());
+        }
+    }
+}
+
+impl<'b, 'a, A: Adapter> IntoIterator for &'b LinkChain<'a, A> {
+    type Item = &'b A::Value;
+    type IntoIter = Iter<'b, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over adapter list members
+///
+/// -`head`  - next links to yield from the front
+/// -`tail`  - next links to yield from the back
+/// -`len`   - members left to yield
+pub struct Iter<'a, A: Adapter> {
+    head: *const Link,
+    tail: *const Link,
+    len: usize,
+    marker: PhantomData<&'a A::Value>,
+}
+
+/// Mutable iterator over adapter list members
+///
+/// -`head`  - next links to yield from the front
+/// -`tail`  - next links to yield from the back
+/// -`len`   - members left to yield
+pub struct IterMut<'a, A: Adapter> {
+    head: *mut Link,
+    tail: *mut Link,
+    len: usize,
+    marker: PhantomData<&'a mut A::Value>,
+}
+
+impl<'a, A: Adapter> Iter<'a, A> {
+    /// Iterator ctor
+    /// -`nurse`   - list sentinel, members live for `'a`
+    unsafe fn from_raw(nurse: *const Link) -> Self {
+        Iter {
+            head: Link::next(nurse),
+            tail: Link::prev(nurse),
+            len: Link::ring_len(nurse),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, A: Adapter> Iterator for Iter<'a, A> {
+    type Item = &'a A::Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        unsafe {
+            let sel: *const Link = self.head;
+            self.head = Link::next(sel);
+            self.len -= 1;
+            Some(&*A::container_of(sel.cast()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, A: Adapter> DoubleEndedIterator for Iter<'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        unsafe {
+            let sel: *const Link = self.tail;
+            self.tail = Link::prev(sel);
+            self.len -= 1;
+            Some(&*A::container_of(sel.cast()))
+        }
+    }
+}
+
+impl<'a, A: Adapter> ExactSizeIterator for Iter<'a, A> {}
+impl<'a, A: Adapter> FusedIterator for Iter<'a, A> {}
+
+impl<'a, A: Adapter> Iterator for IterMut<'a, A> {
+    type Item = Pin<&'a mut A::Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        unsafe {
+            let sel: *mut Link = self.head;
+            self.head = Link::next(sel);
+            self.len -= 1;
+            let value: *mut A::Value = A::container_of(sel.cast()).cast_mut();
+            Some(Pin::new_unchecked(&mut *value))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, A: Adapter> DoubleEndedIterator for IterMut<'a, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        unsafe {
+            let sel: *mut Link = self.tail;
+            self.tail = Link::prev(sel);
+            self.len -= 1;
+            let value: *mut A::Value = A::container_of(sel.cast()).cast_mut();
+            Some(Pin::new_unchecked(&mut *value))
+        }
+    }
+}
+
+impl<'a, A: Adapter> ExactSizeIterator for IterMut<'a, A> {}
+impl<'a, A: Adapter> FusedIterator for IterMut<'a, A> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapter::{Adapter, LinkChain, LinkList, ListLink};
+    use core::pin::{pin, Pin};
+
+    /// Value in two lists at once
+    struct Node {
+        data: u32,
+        all: ListLink,
+        even: ListLink,
+    }
+
+    dll_adapter!(AllAdapter = Node { all });
+    dll_adapter!(EvenAdapter = Node { even });
+
+    fn node(data: u32) -> Pin<Box<Node>> {
+        Box::pin(Node {
+            data,
+            all: ListLink::new(),
+            even: ListLink::new(),
+        })
+    }
+
+    fn collect<A: Adapter<Value = Node>>(list: &LinkList<A>) -> Vec<u32> {
+        unsafe { list.iter_unchecked() }
+            .map(|node| node.data)
+            .collect()
+    }
+
+    #[test]
+    fn adapter_tests() {
+        let mut all = pin!(LinkList::<AllAdapter>::new());
+        let mut even = pin!(LinkList::<EvenAdapter>::new());
+        let mut nodes: Vec<Pin<Box<Node>>> = (0..6).map(node).collect();
+        assert!(all.is_empty());
+
+        for node in nodes.iter_mut() {
+            all.as_mut().push_back(node.as_mut());
+            if 0 == node.data % 2 {
+                even.as_mut().push_front(node.as_mut());
+            }
+        }
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], collect(&all));
+        assert_eq!(vec![4, 2, 0], collect(&even));
+        assert_eq!(3, even.len());
+
+        /* leaving one list keeps the other */
+        EvenAdapter::unlink(nodes[2].as_mut());
+        assert!(!EvenAdapter::is_linked(&nodes[2]));
+        assert!(AllAdapter::is_linked(&nodes[2]));
+        assert_eq!(vec![4, 0], collect(&even));
+
+        /* dropped value leaves all lists */
+        nodes.remove(4);
+        assert_eq!(vec![0, 1, 2, 3, 5], collect(&all));
+        assert_eq!(vec![0], collect(&even));
+        let rev: Vec<u32> = unsafe { all.iter_unchecked() }
+            .rev()
+            .map(|node| node.data)
+            .collect();
+        assert_eq!(vec![5, 3, 2, 1, 0], rev);
+
+        /* value is mutated through the list, it stays pinned */
+        for node in unsafe { even.as_mut().iter_mut_unchecked() } {
+            unsafe { node.get_unchecked_mut().data += 10 };
+        }
+        assert_eq!(10, nodes[0].data);
+
+        all.as_mut().clear();
+        assert!(all.is_empty());
+        assert!(nodes.iter().all(|node| !AllAdapter::is_linked(node)));
+        assert!(EvenAdapter::is_linked(&nodes[0]));
+    }
+
+    #[test]
+    fn adapter_chain_tests() {
+        let nodes: Vec<Pin<Box<Node>>> = (0..4).map(node).collect();
+        {
+            let mut all = pin!(LinkChain::<AllAdapter>::new());
+            let mut even = pin!(LinkChain::<EvenAdapter>::new());
+            assert!(all.is_empty());
+            for node in nodes.iter() {
+                all.as_mut().push_front(node.as_ref());
+                if 0 == node.data % 2 {
+                    even.as_mut().push_back(node.as_ref());
+                }
+            }
+            let fwd: Vec<u32> = all.iter().map(|node| node.data).collect();
+            assert_eq!(vec![3, 2, 1, 0], fwd);
+            let bwd: Vec<u32> = (&*even).into_iter().rev().map(|node| node.data).collect();
+            assert_eq!(vec![2, 0], bwd);
+            assert_eq!(2, even.len());
+
+            /* value of other chain is not removed */
+            assert!(even.as_mut().remove(&nodes[0]));
+            assert!(!even.as_mut().remove(&nodes[1]));
+            assert!(AllAdapter::is_linked(&nodes[0]) && !EvenAdapter::is_linked(&nodes[0]));
+            assert_eq!(1, even.len());
+            all.as_mut().clear();
+            assert!(all.is_empty());
+        }
+        /* dropped chain unlinks its members */
+        assert!(nodes.iter().all(|node| !EvenAdapter::is_linked(node)));
+    }
+
+    #[test]
+    fn adapter_drop_tests() {
+        let mut nodes: Vec<Pin<Box<Node>>> = (0..3).map(node).collect();
+        {
+            let mut all = Box::pin(LinkList::<AllAdapter>::new());
+            for node in nodes.iter_mut() {
+                all.as_mut().push_front(node.as_mut());
+            }
+            assert_eq!(vec![2, 1, 0], collect(&all));
+        }
+        /* dropped list unlinks its members */
+        assert!(nodes.iter().all(|node| !AllAdapter::is_linked(node)));
+        let mut other = pin!(LinkList::<AllAdapter>::new());
+        other.as_mut().push_back(nodes[1].as_mut());
+        assert_eq!(vec![1], collect(&other));
+    }
+}
@@ -0,0 +1,269 @@
+//! Search, filter and sort for owning double linked list
+//! all operations work on node links, payload is never
+//! moved or reallocated. Every relink keeps the ring valid,
+//! so a panic in user closure leaves a consistent list.
+use crate::list::DllList;
+use crate::pool::{Global, NodeAlloc};
+use crate::{Dll, Link};
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+
+/// Node payload
+unsafe fn data<'a, T>(node: *mut Link) -> &'a mut T {
+    &mut (*node.cast::<Dll<T>>()).data
+}
+
+impl<T, A: NodeAlloc<T>> DllList<T, A> {
+    /// Find first data matching the predicate
+    /// -`pred`    - predicate
+    /// -`return`  - data, or None, if nothing matches
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut pred: P) -> Option<&T> {
+        self.iter().find(|data: &&T| pred(data))
+    }
+
+    /// Find first data matching the predicate, for modification
+    /// -`pred`    - predicate
+    /// -`return`  - data, or None, if nothing matches
+    pub fn find_mut<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<&mut T> {
+        self.iter_mut().find(|data: &&mut T| pred(data))
+    }
+
+    /// Find first data, for which the map gives a result
+    /// -`f`       - map
+    /// -`return`  - first map result, or None
+    pub fn find_map<B, F: FnMut(&T) -> Option<B>>(&self, f: F) -> Option<B> {
+        self.iter().find_map(f)
+    }
+
+    /// Keep only data matching the predicate, order is kept
+    /// -`pred`    - predicate, false drops the node
+    pub fn retain<P: FnMut(&T) -> bool>(&mut self, mut pred: P) {
+        self.drain_filter(|data: &mut T| !pred(data)).for_each(drop);
+    }
+
+    /// Lazily take out data matching the predicate,
+    /// nodes not visited before the iterator is dropped stay in the list
+    /// -`pred`    - predicate, true takes the node out
+    /// -`return`  - iterator over taken data
+    pub fn drain_filter<P: FnMut(&mut T) -> bool>(&mut self, pred: P) -> DrainFilter<'_, T, P, A> {
+        let sel: *mut Link = unsafe { Link::next(self.nurse.as_ptr()) };
+        DrainFilter {
+            list: self,
+            sel,
+            pred,
+        }
+    }
+
+    /// Drop consecutive nodes of the same bucket, first one is kept
+    /// -`same`    - called with (node, kept prev node), true drops the node
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same: F) {
+        if self.len < 2 {
+            return;
+        }
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            let mut kept: *mut Link = Link::next(nurse);
+            let mut sel: *mut Link = Link::next(kept);
+            while nurse != sel {
+                let next: *mut Link = Link::next(sel);
+                if same(data(sel), data(kept)) {
+                    drop(self.unlink_node(sel));
+                } else {
+                    kept = sel;
+                }
+                sel = next;
+            }
+        }
+    }
+
+    /// Drop consecutive nodes with the same key
+    /// -`key`     - key of the node
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a: &mut T, b: &mut T| key(a) == key(b));
+    }
+
+    /// Stable in-place merge sort, O(n log n) compares
+    /// -`cmp`     - data comparator
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut cmp: F) {
+        let len: usize = self.len;
+        let mut width: usize = 1;
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            while width < len {
+                /* merge pairs of sorted runs of `width` nodes */
+                let mut run: *mut Link = Link::next(nurse);
+                let mut merged: usize = 0;
+                while merged < len {
+                    let a_len: usize = width.min(len - merged);
+                    let b_len: usize = width.min(len - merged - a_len);
+                    let mut a: *mut Link = run;
+                    let mut b: *mut Link = run;
+                    for _ in 0..a_len {
+                        b = Link::next(b);
+                    }
+                    let (mut a_left, mut b_left): (usize, usize) = (a_len, b_len);
+                    while 0 != a_left && 0 != b_left {
+                        if Ordering::Greater == cmp(data(a), data(b)) {
+                            /* move b before a, equal keep order */
+                            let b_next: *mut Link = Link::next(b);
+                            Link::detach(b);
+                            Link::link_between(b, Link::prev(a), a);
+                            b = b_next;
+                            b_left -= 1;
+                        } else {
+                            a = Link::next(a);
+                            a_left -= 1;
+                        }
+                    }
+                    for _ in 0..b_left {
+                        b = Link::next(b);
+                    }
+                    merged += a_len + b_len;
+                    run = b;
+                }
+                width *= 2;
+            }
+        }
+    }
+
+    /// Stable in-place merge sort by key
+    /// -`key`     - key of the node
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a: &T, b: &T| key(a).cmp(&key(b)));
+    }
+}
+
+impl<T: Ord, A: NodeAlloc<T>> DllList<T, A> {
+    /// Stable in-place merge sort
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+}
+
+impl<T: PartialEq, A: NodeAlloc<T>> DllList<T, A> {
+    /// Drop consecutive equal nodes
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a: &mut T, b: &mut T| a == b);
+    }
+}
+
+/// Iterator taking matching data out of the list
+///
+/// -`list`  - list to take data from
+/// -`sel`   - next node to check
+/// -`pred`  - predicate, true takes the node out
+pub struct DrainFilter<'a, T, P: FnMut(&mut T) -> bool, A: NodeAlloc<T> = Global> {
+    list: &'a mut DllList<T, A>,
+    sel: *mut Link,
+    pred: P,
+}
+
+impl<'a, T, P: FnMut(&mut T) -> bool, A: NodeAlloc<T>> Iterator for DrainFilter<'a, T, P, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            while self.list.nurse.as_ptr() != self.sel {
+                let sel: *mut Link = self.sel;
+                self.sel = Link::next(sel);
+                if (self.pred)(data(sel)) {
+                    return Some(self.list.unlink_node(sel));
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.list.len))
+    }
+}
+
+impl<'a, T, P: FnMut(&mut T) -> bool, A: NodeAlloc<T>> FusedIterator for DrainFilter<'a, T, P, A> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::list::DllList;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn collect<T: Copy>(list: &DllList<T>) -> Vec<T> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn find_tests() {
+        let mut list: DllList<u32> = (0..5).collect();
+        assert_eq!(Some(&3), list.find(|&data| data > 2));
+        assert_eq!(None, list.find(|&data| data > 10));
+        *list.find_mut(|&data| 1 == data).unwrap() = 10;
+        assert_eq!(
+            Some(20),
+            list.find_map(|&data| (data > 5).then_some(data * 2))
+        );
+    }
+
+    #[test]
+    fn filter_tests() {
+        let mut list: DllList<u32> = (0..10).collect();
+        list.retain(|&data| 0 != data % 3);
+        assert_eq!(vec![1, 2, 4, 5, 7, 8], collect(&list));
+
+        let even: Vec<u32> = list.drain_filter(|data| 0 == *data % 2).collect();
+        assert_eq!(vec![2, 4, 8], even);
+        assert_eq!(vec![1, 5, 7], collect(&list));
+
+        /* dropped iterator leaves the rest in place */
+        assert_eq!(Some(1), list.drain_filter(|_| true).next());
+        assert_eq!(vec![5, 7], collect(&list));
+
+        let mut list: DllList<u32> = [1, 1, 2, 2, 2, 1, 3, 3].into_iter().collect();
+        list.dedup();
+        assert_eq!(vec![1, 2, 1, 3], collect(&list));
+        list.dedup_by_key(|data| *data % 2);
+        assert_eq!(vec![1, 2, 1], collect(&list));
+    }
+
+    #[test]
+    fn sort_tests() {
+        let mut list: DllList<(u32, u32)> =
+            [(3, 0), (1, 0), (2, 0), (1, 1), (3, 1), (0, 0), (1, 2)]
+                .into_iter()
+                .collect();
+        list.sort_by_key(|&(key, _)| key);
+        /* equal keys keep their order */
+        assert_eq!(
+            vec![(0, 0), (1, 0), (1, 1), (1, 2), (2, 0), (3, 0), (3, 1)],
+            collect(&list)
+        );
+        assert_eq!(Some(&(3, 1)), list.back());
+        assert_eq!(
+            vec![(3, 1), (3, 0)],
+            list.iter().rev().take(2).copied().collect::<Vec<_>>()
+        );
+
+        let mut list: DllList<u32> = (0..100).map(|data| (data * 37) % 101).collect();
+        list.sort();
+        let mut model: Vec<u32> = (0..100).map(|data| (data * 37) % 101).collect();
+        model.sort();
+        assert_eq!(model, collect(&list));
+    }
+
+    #[test]
+    fn sort_panic_tests() {
+        /* panicking comparator leaves all nodes linked */
+        let mut list: DllList<u32> = (0..16).rev().collect();
+        let mut calls: usize = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                calls += 1;
+                assert!(calls < 20);
+                a.cmp(b)
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(16, list.len());
+        let mut data: Vec<u32> = collect(&list);
+        data.sort();
+        assert_eq!((0..16).collect::<Vec<u32>>(), data);
+    }
+}
@@ -0,0 +1,730 @@
+//! Double linked list in a generational arena
+//! links are slab indices instead of pointers,
+//! so links are handled without unsafe code, only `IterMut`
+//! reaches slots through raw slab pointer to hand out disjoint
+//! references, and a handle to removed node is detected as stale.
+//! Slot, which generation would wrap, is retired and never reused,
+//! so old handles can't reach its future nodes.
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// No node index
+const NIL: usize = usize::MAX;
+
+/// Handle to arena node
+///
+/// -`index`       - slot in the slab
+/// -`generation`  - slot generation, when node was added
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+/// Arena slab slot
+///
+/// -`Used`  - slot carries a list node
+/// -`Free`  - slot is in free chain, `next_free` is NIL for the last one,
+///            retired slot with the last generation is out of the chain
+#[derive(Clone)]
+enum Slot<T> {
+    Used {
+        generation: u32,
+        data: T,
+        next: usize,
+        prev: usize,
+    },
+    Free {
+        generation: u32,
+        next_free: usize,
+    },
+}
+
+/// Double linked list with nodes stored in `Vec` slab
+///
+/// -`slots`   - node storage
+/// -`head`    - head slot, NIL if list is empty
+/// -`tail`    - tail slot, NIL if list is empty
+/// -`free`    - first free slot, NIL if there is no free slots
+/// -`len`     - nodes count
+#[derive(Clone)]
+pub struct DllArena<T> {
+    slots: Vec<Slot<T>>,
+    head: usize,
+    tail: usize,
+    free: usize,
+    len: usize,
+}
+
+impl<T> DllArena<T> {
+    /// Arena ctor
+    /// -`return`  - new empty arena list
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Arena ctor, slab is preallocated
+    /// -`capacity`  - nodes to preallocate
+    /// -`return`    - new empty arena list
+    pub fn with_capacity(capacity: usize) -> Self {
+        DllArena {
+            slots: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+            free: NIL,
+            len: 0,
+        }
+    }
+
+    /// Nodes count
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true, if list have no nodes
+    pub fn is_empty(&self) -> bool {
+        0 == self.len
+    }
+
+    /// Add data to head
+    /// -`data`    - data to add
+    /// -`return`  - handle to the new node
+    pub fn push_front(&mut self, data: T) -> Handle {
+        let index: usize = self.alloc(data);
+        self.link_between(index, NIL, self.head);
+        self.handle(index)
+    }
+
+    /// Add data to tail
+    /// -`data`    - data to add
+    /// -`return`  - handle to the new node
+    pub fn push_back(&mut self, data: T) -> Handle {
+        let index: usize = self.alloc(data);
+        self.link_between(index, self.tail, NIL);
+        self.handle(index)
+    }
+
+    /// Take data from head
+    /// -`return`  - head data, or None, if list is empty
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head: Handle = self.head_handle()?;
+        self.unlink(head)
+    }
+
+    /// Take data from tail
+    /// -`return`  - tail data, or None, if list is empty
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail: Handle = self.tail_handle()?;
+        self.unlink(tail)
+    }
+
+    /// Take node out of the list, its slot is reused later,
+    /// unless its generation is exhausted
+    /// -`handle`  - node to unlink
+    /// -`return`  - node data, or None, if handle is stale
+    pub fn unlink(&mut self, handle: Handle) -> Option<T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        self.detach(handle.index);
+        let retired: bool = u32::MAX == handle.generation;
+        let slot: Slot<T> = core::mem::replace(
+            &mut self.slots[handle.index],
+            Slot::Free {
+                generation: if retired {
+                    handle.generation
+                } else {
+                    handle.generation + 1
+                },
+                next_free: if retired { NIL } else { self.free },
+            },
+        );
+        if !retired {
+            self.free = handle.index;
+        }
+        self.len -= 1;
+        match slot {
+            Slot::Used { data, .. } => Some(data),
+            Slot::Free { .. } => None,
+        }
+    }
+
+    /// Move node to head
+    /// -`handle`  - node to move
+    /// -`return`  - false, if handle is stale
+    pub fn move_front(&mut self, handle: Handle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+        self.detach(handle.index);
+        self.link_between(handle.index, NIL, self.head);
+        true
+    }
+
+    /// Move node to tail
+    /// -`handle`  - node to move
+    /// -`return`  - false, if handle is stale
+    pub fn move_back(&mut self, handle: Handle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+        self.detach(handle.index);
+        self.link_between(handle.index, self.tail, NIL);
+        true
+    }
+
+    /// Return true, if handle points to a node of the list
+    pub fn contains(&self, handle: Handle) -> bool {
+        matches!(
+            self.slots.get(handle.index),
+            Some(Slot::Used { generation, .. }) if *generation == handle.generation
+        )
+    }
+
+    /// Node data
+    /// -`handle`  - node to access
+    /// -`return`  - node data, or None, if handle is stale
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        match self.slots.get(handle.index) {
+            Some(Slot::Used {
+                generation, data, ..
+            }) if *generation == handle.generation => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Mutable node data
+    /// -`handle`  - node to access
+    /// -`return`  - node data, or None, if handle is stale
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        match self.slots.get_mut(handle.index) {
+            Some(Slot::Used {
+                generation, data, ..
+            }) if *generation == handle.generation => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Handle to head node
+    pub fn head_handle(&self) -> Option<Handle> {
+        (NIL != self.head).then(|| self.handle(self.head))
+    }
+
+    /// Handle to tail node
+    pub fn tail_handle(&self) -> Option<Handle> {
+        (NIL != self.tail).then(|| self.handle(self.tail))
+    }
+
+    /// Handle to the node after selected one
+    /// -`handle`  - selected node
+    /// -`return`  - next node, or None at tail, or if handle is stale
+    pub fn next_handle(&self, handle: Handle) -> Option<Handle> {
+        let next: usize = self.links(handle)?.0;
+        (NIL != next).then(|| self.handle(next))
+    }
+
+    /// Handle to the node before selected one
+    /// -`handle`  - selected node
+    /// -`return`  - prev node, or None at head, or if handle is stale
+    pub fn prev_handle(&self, handle: Handle) -> Option<Handle> {
+        let prev: usize = self.links(handle)?.1;
+        (NIL != prev).then(|| self.handle(prev))
+    }
+
+    /// Head data
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// Tail data
+    pub fn back(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    /// Drop all nodes, slab memory is kept
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Find first data matching the predicate
+    /// -`pred`    - predicate
+    /// -`return`  - data, or None, if nothing matches
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut pred: P) -> Option<&T> {
+        self.iter().find(|data: &&T| pred(data))
+    }
+
+    /// Find first data matching the predicate, for modification
+    /// -`pred`    - predicate
+    /// -`return`  - data, or None, if nothing matches
+    pub fn find_mut<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<&mut T> {
+        self.iter_mut().find(|data: &&mut T| pred(data))
+    }
+
+    /// Keep only data matching the predicate, order is kept,
+    /// handles of kept nodes stay valid
+    /// -`pred`    - predicate, false drops the node
+    pub fn retain<P: FnMut(&T) -> bool>(&mut self, mut pred: P) {
+        let mut sel: Option<Handle> = self.head_handle();
+        while let Some(handle) = sel {
+            sel = self.next_handle(handle);
+            if !self.get(handle).is_some_and(&mut pred) {
+                self.unlink(handle);
+            }
+        }
+    }
+
+    /// Stable sort, nodes are relinked, so handles stay valid
+    /// -`cmp`     - data comparator
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut cmp: F) {
+        let mut order: Vec<usize> = Vec::with_capacity(self.len);
+        let mut index: usize = self.head;
+        while NIL != index {
+            order.push(index);
+            index = self.links(self.handle(index)).map_or(NIL, |(next, _)| next);
+        }
+        order.sort_by(
+            |&a: &usize, &b: &usize| match (&self.slots[a], &self.slots[b]) {
+                (Slot::Used { data: a, .. }, Slot::Used { data: b, .. }) => cmp(a, b),
+                _ => Ordering::Equal,
+            },
+        );
+        self.head = NIL;
+        self.tail = NIL;
+        for index in order {
+            self.link_between(index, self.tail, NIL);
+        }
+    }
+
+    /// Stable sort by key
+    /// -`key`     - key of the node
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a: &T, b: &T| key(a).cmp(&key(b)));
+    }
+
+    /// Iterate over the list from head to tail
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            arena: self,
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+        }
+    }
+
+    /// Mutably iterate over the list from head to tail
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.as_mut_ptr(),
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /*** slab helpers ***/
+
+    /// Handle for used slot
+    fn handle(&self, index: usize) -> Handle {
+        let generation: u32 = match &self.slots[index] {
+            Slot::Used { generation, .. } | Slot::Free { generation, .. } => *generation,
+        };
+        Handle { index, generation }
+    }
+
+    /// Next and prev slots of the node
+    fn links(&self, handle: Handle) -> Option<(usize, usize)> {
+        match self.slots.get(handle.index) {
+            Some(Slot::Used {
+                generation,
+                next,
+                prev,
+                ..
+            }) if *generation == handle.generation => Some((*next, *prev)),
+            _ => None,
+        }
+    }
+
+    /// Put data into free slot, or grow the slab
+    fn alloc(&mut self, data: T) -> usize {
+        let node = |generation: u32| Slot::Used {
+            generation,
+            data,
+            next: NIL,
+            prev: NIL,
+        };
+        self.len += 1;
+        if NIL == self.free {
+            self.slots.push(node(0));
+            return self.slots.len() - 1;
+        }
+        let index: usize = self.free;
+        if let Slot::Free {
+            generation,
+            next_free,
+        } = self.slots[index]
+        {
+            self.free = next_free;
+            self.slots[index] = node(generation);
+        }
+        index
+    }
+
+    /// Set one link of used slot
+    fn set_links(&mut self, index: usize, new_next: Option<usize>, new_prev: Option<usize>) {
+        if let Slot::Used { next, prev, .. } = &mut self.slots[index] {
+            if let Some(new_next) = new_next {
+                *next = new_next;
+            }
+            if let Some(new_prev) = new_prev {
+                *prev = new_prev;
+            }
+        }
+    }
+
+    /// Insert node between two adjacent nodes, NIL is list end
+    fn link_between(&mut self, index: usize, prev: usize, next: usize) {
+        self.set_links(index, Some(next), Some(prev));
+        if NIL == prev {
+            self.head = index;
+        } else {
+            self.set_links(prev, Some(index), None);
+        }
+        if NIL == next {
+            self.tail = index;
+        } else {
+            self.set_links(next, None, Some(index));
+        }
+    }
+
+    /// Take node out of its chain, keep the chain linked
+    fn detach(&mut self, index: usize) {
+        let (next, prev): (usize, usize) = match self.slots[index] {
+            Slot::Used { next, prev, .. } => (next, prev),
+            Slot::Free { .. } => return,
+        };
+        if NIL == prev {
+            self.head = next;
+        } else {
+            self.set_links(prev, Some(next), None);
+        }
+        if NIL == next {
+            self.tail = prev;
+        } else {
+            self.set_links(next, None, Some(prev));
+        }
+        self.set_links(index, Some(NIL), Some(NIL));
+    }
+}
+
+impl<T> Default for DllArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Extend<T> for DllArena<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push_back(data);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for DllArena<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut arena: Self = Self::new();
+        arena.extend(iter);
+        arena
+    }
+}
+
+/// Shared arena list iterator
+///
+/// -`head`  - next slot to yield from the front
+/// -`tail`  - next slot to yield from the back
+/// -`len`   - nodes left to yield
+pub struct Iter<'a, T> {
+    arena: &'a DllArena<T>,
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+/// Mutable arena list iterator
+///
+/// -`slots` - slab, every used slot is yielded at most once
+/// -`head`  - next slot to yield from the front
+/// -`tail`  - next slot to yield from the back
+/// -`len`   - nodes left to yield
+pub struct IterMut<'a, T> {
+    slots: *mut Slot<T>,
+    head: usize,
+    tail: usize,
+    len: usize,
+    marker: PhantomData<&'a mut DllArena<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        match &self.arena.slots[self.head] {
+            Slot::Used { data, next, .. } => {
+                self.head = *next;
+                Some(data)
+            }
+            Slot::Free { .. } => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        match &self.arena.slots[self.tail] {
+            Slot::Used { data, prev, .. } => {
+                self.tail = *prev;
+                Some(data)
+            }
+            Slot::Free { .. } => None,
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        /* chain visits every used slot once, so references do not alias */
+        match unsafe { &mut *self.slots.add(self.head) } {
+            Slot::Used { data, next, .. } => {
+                self.head = *next;
+                Some(data)
+            }
+            Slot::Free { .. } => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        match unsafe { &mut *self.slots.add(self.tail) } {
+            Slot::Used { data, prev, .. } => {
+                self.tail = *prev;
+                Some(data)
+            }
+            Slot::Free { .. } => None,
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+/// Owning arena list iterator
+pub struct IntoIter<T> {
+    arena: DllArena<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.arena.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.arena.len, Some(self.arena.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.arena.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for DllArena<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { arena: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DllArena<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DllArena<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{DllArena, Handle, Slot};
+    use devices::smartsocket::SmartSocket;
+    use devices::smartthermometer::SmartThermometer;
+    use devices::{SmartDevice, SmartDeviceAccess};
+
+    fn collect(arena: &DllArena<u32>) -> Vec<u32> {
+        arena.iter().copied().collect()
+    }
+
+    #[test]
+    fn arena_tests() {
+        let mut arena: DllArena<u32> = DllArena::new();
+        let h1: Handle = arena.push_back(1);
+        let h2: Handle = arena.push_back(2);
+        let h0: Handle = arena.push_front(0);
+        assert_eq!(vec![0, 1, 2], collect(&arena));
+        assert_eq!(
+            vec![2, 1, 0],
+            arena.iter().rev().copied().collect::<Vec<u32>>()
+        );
+        assert_eq!(Some(h1), arena.next_handle(h0));
+        assert_eq!(Some(h1), arena.prev_handle(h2));
+        assert_eq!(None, arena.prev_handle(h0));
+
+        assert!(arena.move_front(h2));
+        assert!(arena.move_back(h0));
+        assert_eq!(vec![2, 1, 0], collect(&arena));
+
+        for data in arena.iter_mut() {
+            *data += 10;
+        }
+        assert_eq!(Some(11), arena.unlink(h1));
+        assert_eq!(vec![12, 10], collect(&arena));
+        assert_eq!(Some(12), arena.pop_front());
+        assert_eq!(Some(10), arena.pop_back());
+        assert!(arena.is_empty());
+        assert_eq!(None, arena.pop_front());
+    }
+
+    #[test]
+    fn arena_stale_handle_tests() {
+        let mut arena: DllArena<u32> = (0..3).collect();
+        let head: Handle = arena.head_handle().unwrap();
+        assert_eq!(Some(0), arena.unlink(head));
+
+        /* slot is reused, old handle must not reach the new node */
+        let fresh: Handle = arena.push_back(7);
+        assert!(!arena.contains(head));
+        assert_eq!(None, arena.get(head));
+        assert_eq!(None, arena.get_mut(head));
+        assert_eq!(None, arena.unlink(head));
+        assert!(!arena.move_front(head));
+        assert_eq!(None, arena.next_handle(head));
+        assert_eq!(Some(&7), arena.get(fresh));
+        assert_eq!(vec![1, 2, 7], collect(&arena));
+
+        arena.clear();
+        assert!(!arena.contains(fresh));
+        assert_eq!(0, arena.iter().len());
+        assert_eq!(Vec::<u32>::new(), arena.into_iter().collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn arena_room_tests() {
+        /* room of devices, kept sorted by name */
+        let mut devs: DllArena<SmartDevice> = DllArena::new();
+        let kettle: Handle = devs.push_back(SmartDevice::from(SmartSocket::new("kettle")));
+        devs.push_back(SmartDevice::nodev());
+        devs.push_back(SmartDevice::from(SmartThermometer::new("hall")));
+        devs.push_back(SmartDevice::from(SmartSocket::new("boiler")));
+        devs.sort_by_key(SmartDevice::name);
+        devs.retain(|dev: &SmartDevice| !dev.is_nodev());
+        let names: Vec<String> = devs.iter().map(SmartDevice::name).collect();
+        assert_eq!(vec!["boiler", "hall", "kettle"], names);
+        assert_eq!(Some(kettle), devs.tail_handle());
+
+        let dev: &mut SmartDevice = devs
+            .find_mut(|dev: &SmartDevice| "hall" == dev.name())
+            .unwrap();
+        dev.update();
+        assert!(devs
+            .find(|dev: &SmartDevice| "garage" == dev.name())
+            .is_none());
+    }
+
+    #[test]
+    fn arena_sort_tests() {
+        let mut arena: DllArena<u32> = [3, 1, 2, 1].into_iter().collect();
+        let first_one: Handle = arena.next_handle(arena.head_handle().unwrap()).unwrap();
+        arena.sort_by_key(|&data: &u32| data);
+        assert_eq!(vec![1, 1, 2, 3], collect(&arena));
+        /* sort is stable and handles follow their nodes */
+        assert_eq!(Some(first_one), arena.head_handle());
+        assert_eq!(
+            vec![3, 2, 1, 1],
+            arena.iter().rev().copied().collect::<Vec<u32>>()
+        );
+        arena.retain(|&data: &u32| 1 != data);
+        assert_eq!(vec![2, 3], collect(&arena));
+        assert!(!arena.contains(first_one));
+    }
+
+    #[test]
+    fn arena_generation_wrap_tests() {
+        let mut arena: DllArena<u32> = DllArena::new();
+        let old: Handle = arena.push_back(0);
+        arena.unlink(old);
+        /* slot reaches its last generation */
+        arena.slots[old.index] = Slot::Free {
+            generation: u32::MAX,
+            next_free: super::NIL,
+        };
+        let last: Handle = arena.push_back(1);
+        assert_eq!(old.index, last.index);
+        assert_eq!(Some(1), arena.unlink(last));
+
+        /* exhausted slot is retired, not reused */
+        let fresh: Handle = arena.push_back(2);
+        assert_ne!(old.index, fresh.index);
+        assert!(!arena.contains(old) && !arena.contains(last));
+        assert_eq!(vec![2], collect(&arena));
+    }
+}
@@ -0,0 +1,173 @@
+//! Borrowing chain of `Dll` nodes
+//! `DllChain` is a pinned sentinel, that mutably borrows
+//! every node added into it. While the chain is alive its nodes
+//! can't be reached, unlinked or dropped through their own handles,
+//! so the chain is iterated safely. Dropped chain unlinks all nodes.
+use crate::iter::{Iter, IterMut};
+use crate::{Dll, Link};
+use core::marker::PhantomData;
+use core::pin::Pin;
+
+/// Pinned sentinel, that borrows its nodes
+///
+/// -`nurse`    - sentinel links
+/// -`len`      - nodes count
+/// -`marker`   - nodes are borrowed for `'a`
+#[repr(C)]
+pub struct DllChain<'a, T> {
+    nurse: Link,
+    len: usize,
+    marker: PhantomData<Pin<&'a mut Dll<T>>>,
+}
+
+impl<'a, T> DllChain<'a, T> {
+    /// Chain ctor, chain is pinned before nodes are added
+    /// -`return`  - new empty chain
+    pub const fn new() -> Self {
+        DllChain {
+            nurse: Link::new(),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Nodes count
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true, if chain have no nodes
+    pub fn is_empty(&self) -> bool {
+        0 == self.len
+    }
+
+    /// Add node to head,
+    /// node is unlinked from its previous chain first
+    ///
+    /// -`node`    - node, borrowed until the chain is dropped
+    pub fn push_front(self: Pin<&mut Self>, node: Pin<&'a mut Dll<T>>) {
+        unsafe {
+            let this: &mut Self = self.get_unchecked_mut();
+            let nurse: *mut Link = &mut this.nurse;
+            let node: *mut Link = (node.get_unchecked_mut() as *mut Dll<T>).cast();
+            Link::detach(node);
+            Link::self_link(nurse);
+            Link::link_between(node, nurse, Link::next(nurse));
+            this.len += 1;
+        }
+    }
+
+    /// Add node to tail,
+    /// node is unlinked from its previous chain first
+    ///
+    /// -`node`    - node, borrowed until the chain is dropped
+    pub fn push_back(self: Pin<&mut Self>, node: Pin<&'a mut Dll<T>>) {
+        unsafe {
+            let this: &mut Self = self.get_unchecked_mut();
+            let nurse: *mut Link = &mut this.nurse;
+            let node: *mut Link = (node.get_unchecked_mut() as *mut Dll<T>).cast();
+            Link::detach(node);
+            Link::self_link(nurse);
+            Link::link_between(node, Link::prev(nurse), nurse);
+            this.len += 1;
+        }
+    }
+
+    /// Iterate over the chain
+    /// -`return`  - iterator over nodes payload
+    pub fn iter(&self) -> Iter<'_, T> {
+        /* every member is borrowed by the chain, so nothing else can touch it */
+        unsafe { Iter::from_raw(&self.nurse, self.len) }
+    }
+
+    /// Mutably iterate over the chain
+    /// -`return`  - iterator over nodes payload
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T> {
+        unsafe {
+            let this: &mut Self = self.get_unchecked_mut();
+            IterMut::from_raw(&mut this.nurse, this.len)
+        }
+    }
+}
+
+impl<'a, T> Drop for DllChain<'a, T> {
+    /// Unlink all nodes, so they are free, when borrow ends
+    fn drop(&mut self) {
+        let nurse: *mut Link = &mut self.nurse;
+        unsafe {
+            while 0 != self.len {
+                Link::detach(Link::next(nurse));
+                self.len -= 1;
+            }
+            Link::detach(nurse);
+        }
+    }
+}
+
+impl<'a, T> Default for DllChain<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'b, 'a, T> IntoIterator for &'b DllChain<'a, T> {
+    type Item = &'b T;
+    type IntoIter = Iter<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'b, 'a, T> IntoIterator for Pin<&'b mut DllChain<'a, T>> {
+    type Item = &'b mut T;
+    type IntoIter = IterMut<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::DllChain;
+    use crate::Dll;
+    use core::pin::pin;
+
+    #[test]
+    fn chain_tests() {
+        let mut ll_1 = pin!(Dll::from(0x01));
+        let mut ll_2 = pin!(Dll::from(0x02));
+        let mut ll_3 = pin!(Dll::from(0x03));
+        {
+            let mut chain = pin!(DllChain::new());
+            assert_eq!(0, chain.iter().len());
+            chain.as_mut().push_back(ll_2.as_mut());
+            chain.as_mut().push_back(ll_3.as_mut());
+            chain.as_mut().push_front(ll_1.as_mut());
+            assert_eq!(3, chain.len());
+
+            let fwd: Vec<u32> = chain.iter().copied().collect();
+            assert_eq!(vec![1, 2, 3], fwd);
+            let bwd: Vec<u32> = (&*chain).into_iter().rev().copied().collect();
+            assert_eq!(vec![3, 2, 1], bwd);
+            for data in chain.as_mut() {
+                *data *= 10;
+            }
+            let fwd: Vec<u32> = chain.iter().copied().collect();
+            assert_eq!(vec![10, 20, 30], fwd);
+        }
+        /* dropped chain frees its nodes */
+        assert!(!ll_1.is_linked() && !ll_2.is_linked() && !ll_3.is_linked());
+        assert_eq!(20, ll_2.data);
+
+        /* node is moved from raw chain into borrowing one */
+        let mut nurse = pin!(Dll::from(0));
+        nurse.as_mut().addt(ll_1.as_mut());
+        nurse.as_mut().addt(ll_2.as_mut());
+        let mut chain = pin!(DllChain::new());
+        chain.as_mut().push_back(ll_2.as_mut());
+        assert_eq!(vec![&20], chain.iter().collect::<Vec<&u32>>());
+        assert!(!nurse.is_last() && !ll_1.is_last());
+    }
+}
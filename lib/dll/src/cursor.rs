@@ -0,0 +1,436 @@
+//! Cursors over owning double linked list
+//! cursor points to a node, or to the sentinel (nurse),
+//! that lies between tail and head of the ring
+use crate::list::DllList;
+use crate::pool::{AllocError, Global, NodeAlloc};
+use crate::{Dll, Link};
+
+/// Shared cursor
+///
+/// -`list`     - list the cursor walks over
+/// -`current`  - current node, or sentinel
+/// -`index`    - current node position, `len` at sentinel
+pub struct Cursor<'a, T, A: NodeAlloc<T> = Global> {
+    list: &'a DllList<T, A>,
+    current: *const Link,
+    index: usize,
+}
+
+/// Mutable cursor, can edit the list around current node
+///
+/// -`list`     - list the cursor walks over
+/// -`current`  - current node, or sentinel
+/// -`index`    - current node position, `len` at sentinel
+pub struct CursorMut<'a, T, A: NodeAlloc<T> = Global> {
+    list: &'a mut DllList<T, A>,
+    current: *mut Link,
+    index: usize,
+}
+
+impl<'a, T, A: NodeAlloc<T>> Cursor<'a, T, A> {
+    /// Cursor ctor
+    /// -`list`     - list to walk over
+    /// -`current`  - node of the list, or its sentinel
+    /// -`index`    - node position, `len` at sentinel
+    pub(crate) unsafe fn from_raw(
+        list: &'a DllList<T, A>,
+        current: *const Link,
+        index: usize,
+    ) -> Self {
+        Cursor {
+            list,
+            current,
+            index,
+        }
+    }
+
+    /// Current node position
+    /// -`return`  - position from head, or None at sentinel
+    pub fn index(&self) -> Option<usize> {
+        if self.is_nurse() {
+            None
+        } else {
+            Some(self.index)
+        }
+    }
+
+    /// Move to the next node, sentinel is passed after tail
+    pub fn move_next(&mut self) {
+        let was_nurse: bool = self.is_nurse();
+        unsafe {
+            self.current = Link::next(self.current);
+        }
+        self.index = if was_nurse { 0 } else { self.index + 1 };
+    }
+
+    /// Move to the prev node, sentinel is passed before head
+    pub fn move_prev(&mut self) {
+        let was_nurse: bool = self.is_nurse();
+        unsafe {
+            self.current = Link::prev(self.current);
+        }
+        self.index = if was_nurse {
+            self.list.len.saturating_sub(1)
+        } else if 0 == self.index {
+            self.list.len
+        } else {
+            self.index - 1
+        };
+    }
+
+    /// Current node payload
+    /// -`return`  - payload, or None at sentinel
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { Self::data(self.list, self.current) }
+    }
+
+    /// Next node payload
+    /// -`return`  - payload, or None, if next is sentinel
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe { Self::data(self.list, Link::next(self.current)) }
+    }
+
+    /// Prev node payload
+    /// -`return`  - payload, or None, if prev is sentinel
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe { Self::data(self.list, Link::prev(self.current)) }
+    }
+
+    fn is_nurse(&self) -> bool {
+        self.current == self.list.nurse.as_ptr()
+    }
+
+    unsafe fn data(list: &'a DllList<T, A>, sel: *const Link) -> Option<&'a T> {
+        if sel == list.nurse.as_ptr() {
+            None
+        } else {
+            Some(&(*sel.cast::<Dll<T>>()).data)
+        }
+    }
+}
+
+impl<'a, T, A: NodeAlloc<T>> Clone for Cursor<'a, T, A> {
+    fn clone(&self) -> Self {
+        Cursor {
+            list: self.list,
+            current: self.current,
+            index: self.index,
+        }
+    }
+}
+
+impl<'a, T, A: NodeAlloc<T>> CursorMut<'a, T, A> {
+    /// Cursor ctor
+    /// -`list`     - list to walk over
+    /// -`current`  - node of the list, or its sentinel
+    /// -`index`    - node position, `len` at sentinel
+    pub(crate) unsafe fn from_raw(
+        list: &'a mut DllList<T, A>,
+        current: *mut Link,
+        index: usize,
+    ) -> Self {
+        CursorMut {
+            list,
+            current,
+            index,
+        }
+    }
+
+    /// Current node position
+    /// -`return`  - position from head, or None at sentinel
+    pub fn index(&self) -> Option<usize> {
+        self.as_cursor().index()
+    }
+
+    /// Move to the next node, sentinel is passed after tail
+    pub fn move_next(&mut self) {
+        let (current, index): (*const Link, usize) = {
+            let mut shared: Cursor<'_, T, A> = self.as_cursor();
+            shared.move_next();
+            (shared.current, shared.index)
+        };
+        self.current = current as *mut Link;
+        self.index = index;
+    }
+
+    /// Move to the prev node, sentinel is passed before head
+    pub fn move_prev(&mut self) {
+        let (current, index): (*const Link, usize) = {
+            let mut shared: Cursor<'_, T, A> = self.as_cursor();
+            shared.move_prev();
+            (shared.current, shared.index)
+        };
+        self.current = current as *mut Link;
+        self.index = index;
+    }
+
+    /// Current node payload
+    /// -`return`  - payload, or None at sentinel
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.data(self.current) }
+    }
+
+    /// Next node payload
+    /// -`return`  - payload, or None, if next is sentinel
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe { self.data(Link::next(self.current)) }
+    }
+
+    /// Prev node payload
+    /// -`return`  - payload, or None, if prev is sentinel
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe { self.data(Link::prev(self.current)) }
+    }
+
+    /// Read-only cursor at the same position
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        unsafe { Cursor::from_raw(self.list, self.current, self.index) }
+    }
+
+    /// Insert data after current node,
+    /// at sentinel data becomes new head
+    /// -`data`    - data to insert
+    pub fn insert_after(&mut self, data: T) {
+        unsafe {
+            let next: *mut Link = Link::next(self.current);
+            if self.is_nurse() {
+                self.index += 1;
+            }
+            self.list.link_between(data, self.current, next);
+        }
+    }
+
+    /// Insert data before current node,
+    /// at sentinel data becomes new tail
+    /// -`data`    - data to insert
+    pub fn insert_before(&mut self, data: T) {
+        unsafe {
+            let prev: *mut Link = Link::prev(self.current);
+            self.index += 1;
+            self.list.link_between(data, prev, self.current);
+        }
+    }
+
+    /// Take current node out of the list,
+    /// cursor moves to the next node
+    /// -`return`  - current data, or None at sentinel
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.is_nurse() {
+            return None;
+        }
+        unsafe {
+            let sel: *mut Link = self.current;
+            self.current = Link::next(sel);
+            Some(self.list.unlink_node(sel))
+        }
+    }
+
+    /// Move all nodes of other list after current node, in O(1),
+    /// at sentinel nodes become new head
+    /// -`other`   - list to take nodes from
+    pub fn splice_after(&mut self, mut other: DllList<T, A>) {
+        unsafe {
+            let next: *mut Link = Link::next(self.current);
+            if self.is_nurse() {
+                self.index += other.len();
+            }
+            self.list.splice_chain(&mut other, self.current, next);
+        }
+    }
+
+    /// Move all nodes of other list before current node, in O(1),
+    /// at sentinel nodes become new tail
+    /// -`other`   - list to take nodes from
+    pub fn splice_before(&mut self, mut other: DllList<T, A>) {
+        unsafe {
+            let prev: *mut Link = Link::prev(self.current);
+            self.index += other.len();
+            self.list.splice_chain(&mut other, prev, self.current);
+        }
+    }
+
+    /// Cut all nodes after current node into a new list, in O(1),
+    /// at sentinel the whole list is taken,
+    /// panics, if node memory is exhausted,
+    /// new list takes a node for its sentinel
+    /// -`return`  - list with nodes after current
+    pub fn split_after(&mut self) -> DllList<T, A> {
+        match self.try_split_after() {
+            Ok(list) => list,
+            Err(_) => panic!("node memory is exhausted"),
+        }
+    }
+
+    /// Cut all nodes after current node into a new list, in O(1),
+    /// at sentinel the whole list is taken
+    /// -`return`  - list with nodes after current, or error,
+    ///              if node memory is exhausted, nothing is cut then
+    pub fn try_split_after(&mut self) -> Result<DllList<T, A>, AllocError<()>> {
+        let nurse: *mut Link = self.list.nurse.as_ptr();
+        let count: usize = if self.is_nurse() {
+            self.list.len
+        } else {
+            self.list.len - self.index - 1
+        };
+        unsafe {
+            let chain: DllList<T, A> =
+                self.list
+                    .try_cut_chain(Link::next(self.current), Link::prev(nurse), count)?;
+            if self.is_nurse() {
+                self.index = 0;
+            }
+            Ok(chain)
+        }
+    }
+
+    /// Cut all nodes before current node into a new list, in O(1),
+    /// at sentinel the whole list is taken,
+    /// panics, if node memory is exhausted,
+    /// new list takes a node for its sentinel
+    /// -`return`  - list with nodes before current
+    pub fn split_before(&mut self) -> DllList<T, A> {
+        match self.try_split_before() {
+            Ok(list) => list,
+            Err(_) => panic!("node memory is exhausted"),
+        }
+    }
+
+    /// Cut all nodes before current node into a new list, in O(1),
+    /// at sentinel the whole list is taken
+    /// -`return`  - list with nodes before current, or error,
+    ///              if node memory is exhausted, nothing is cut then
+    pub fn try_split_before(&mut self) -> Result<DllList<T, A>, AllocError<()>> {
+        let nurse: *mut Link = self.list.nurse.as_ptr();
+        let count: usize = if self.is_nurse() {
+            self.list.len
+        } else {
+            self.index
+        };
+        unsafe {
+            let chain: DllList<T, A> =
+                self.list
+                    .try_cut_chain(Link::next(nurse), Link::prev(self.current), count)?;
+            self.index = 0;
+            Ok(chain)
+        }
+    }
+
+    fn is_nurse(&self) -> bool {
+        self.current == self.list.nurse.as_ptr()
+    }
+
+    unsafe fn data(&mut self, sel: *mut Link) -> Option<&mut T> {
+        if sel == self.list.nurse.as_ptr() {
+            None
+        } else {
+            Some(&mut (*sel.cast::<Dll<T>>()).data)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cursor::CursorMut;
+    use crate::list::DllList;
+
+    fn collect(list: &DllList<u32>) -> Vec<u32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn cursor_walk_tests() {
+        let list: DllList<u32> = (0..3).collect();
+        let mut cursor = list.cursor_front();
+        assert_eq!(Some(0), cursor.index());
+        assert_eq!(Some(&0), cursor.current());
+        assert_eq!(None, cursor.peek_prev());
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(Some(2), cursor.index());
+        assert_eq!(None, cursor.peek_next());
+        /* tail -> sentinel -> head */
+        cursor.move_next();
+        assert_eq!(None, cursor.index());
+        assert_eq!(None, cursor.current());
+        cursor.move_next();
+        assert_eq!(Some(&0), cursor.current());
+        cursor.move_prev();
+        assert_eq!(None, cursor.index());
+        cursor.move_prev();
+        assert_eq!(Some(2), cursor.index());
+
+        let empty: DllList<u32> = DllList::new();
+        let mut cursor = empty.cursor_back();
+        assert_eq!(None, cursor.index());
+        cursor.move_prev();
+        assert_eq!(None, cursor.current());
+    }
+
+    #[test]
+    fn cursor_edit_tests() {
+        let mut list: DllList<u32> = (0..3).collect();
+        let mut cursor: CursorMut<'_, u32> = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.insert_before(10);
+        cursor.insert_after(11);
+        assert_eq!(Some(2), cursor.index());
+        *cursor.current().unwrap() += 100;
+        assert_eq!(Some(101), cursor.remove_current());
+        assert_eq!(Some(&mut 11), cursor.current());
+        assert_eq!(Some(2), cursor.index());
+        assert_eq!(vec![0, 10, 11, 2], collect(&list));
+
+        /* insert at sentinel */
+        let mut cursor: CursorMut<'_, u32> = list.cursor_back_mut();
+        cursor.move_next();
+        cursor.insert_after(20);
+        cursor.insert_before(21);
+        assert_eq!(None, cursor.index());
+        assert_eq!(None, cursor.remove_current());
+        cursor.move_prev();
+        assert_eq!(Some(5), cursor.index());
+        assert_eq!(vec![20, 0, 10, 11, 2, 21], collect(&list));
+    }
+
+    #[test]
+    fn cursor_splice_tests() {
+        let mut list: DllList<u32> = (0..4).collect();
+        let mut cursor: CursorMut<'_, u32> = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.splice_after((10..12).collect());
+        cursor.splice_before((20..22).collect());
+        assert_eq!(Some(3), cursor.index());
+        assert_eq!(Some(&mut 1), cursor.current());
+
+        let tail: DllList<u32> = cursor.split_after();
+        let head: DllList<u32> = cursor.split_before();
+        assert_eq!(Some(0), cursor.index());
+        assert_eq!(vec![10, 11, 2, 3], collect(&tail));
+        assert_eq!(vec![0, 20, 21], collect(&head));
+        assert_eq!(vec![1], collect(&list));
+
+        /* whole list split at sentinel */
+        let mut cursor: CursorMut<'_, u32> = list.cursor_back_mut();
+        cursor.move_next();
+        let all: DllList<u32> = cursor.split_after();
+        assert_eq!(vec![1], collect(&all));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn list_append_split_tests() {
+        let mut list: DllList<u32> = (0..3).collect();
+        let mut other: DllList<u32> = (3..6).collect();
+        list.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(6, list.len());
+
+        let mut tail: DllList<u32> = list.split_off(4);
+        assert_eq!(vec![0, 1, 2, 3], collect(&list));
+        assert_eq!(vec![4, 5], collect(&tail));
+        let all: DllList<u32> = list.split_off(0);
+        assert!(list.is_empty());
+        assert_eq!(4, all.len());
+        assert!(tail.split_off(2).is_empty());
+    }
+}
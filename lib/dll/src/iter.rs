@@ -0,0 +1,190 @@
+//! Iterators over double linked list
+//! iteration starts from the sentinel (nurse),
+//! the sentinel itself is not yielded
+use crate::{Dll, Link};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// Shared double linked list iterator
+///
+/// -`head`  - next node to yield from the front
+/// -`tail`  - next node to yield from the back
+/// -`len`   - nodes left to yield
+pub struct Iter<'a, T> {
+    head: *const Link,
+    tail: *const Link,
+    len: usize,
+    marker: PhantomData<&'a Dll<T>>,
+}
+
+/// Mutable double linked list iterator
+///
+/// -`head`  - next node to yield from the front
+/// -`tail`  - next node to yield from the back
+/// -`len`   - nodes left to yield
+pub struct IterMut<'a, T> {
+    head: *mut Link,
+    tail: *mut Link,
+    len: usize,
+    marker: PhantomData<&'a mut Dll<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    /// Iterator ctor
+    ///
+    /// -`nurse`   - list sentinel, all other ring members are `Dll<T>`
+    /// -`len`     - nodes in the ring, sentinel excluded
+    /// -`return`  - new iterator instance
+    pub(crate) unsafe fn from_raw(nurse: *const Link, len: usize) -> Self {
+        Iter {
+            head: Link::next(nurse),
+            tail: Link::prev(nurse),
+            len,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> IterMut<'a, T> {
+    /// Iterator ctor
+    ///
+    /// -`nurse`   - list sentinel, all other ring members are `Dll<T>`
+    /// -`len`     - nodes in the ring, sentinel excluded
+    /// -`return`  - new iterator instance
+    pub(crate) unsafe fn from_raw(nurse: *mut Link, len: usize) -> Self {
+        IterMut {
+            head: Link::next(nurse),
+            tail: Link::prev(nurse),
+            len,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let sel: *const Dll<T> = self.head.cast();
+            self.head = Link::next(self.head);
+            Some(&(*sel).data)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let sel: *const Dll<T> = self.tail.cast();
+            self.tail = Link::prev(self.tail);
+            Some(&(*sel).data)
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let sel: *mut Dll<T> = self.head.cast();
+            self.head = Link::next(self.head);
+            Some(&mut (*sel).data)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if 0 == self.len {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let sel: *mut Dll<T> = self.tail.cast();
+            self.tail = Link::prev(self.tail);
+            Some(&mut (*sel).data)
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Dll;
+    use core::pin::pin;
+
+    #[test]
+    fn iter_tests() {
+        let mut nurse = pin!(Dll::<u32>::new());
+        let mut ll_1 = pin!(Dll::from(0x01));
+        let mut ll_2 = pin!(Dll::from(0x02));
+        let mut ll_3 = pin!(Dll::from(0x03));
+
+        /* empty list yields nothing */
+        assert_eq!(0, unsafe { nurse.iter() }.len());
+        assert_eq!(None, unsafe { nurse.iter() }.next());
+
+        nurse.as_mut().addt(ll_1.as_mut());
+        nurse.as_mut().addt(ll_2.as_mut());
+        nurse.as_mut().addt(ll_3.as_mut());
+
+        let fwd: Vec<u32> = unsafe { nurse.iter() }.copied().collect();
+        assert_eq!(vec![1, 2, 3], fwd);
+        let bwd: Vec<u32> = unsafe { nurse.iter() }.rev().copied().collect();
+        assert_eq!(vec![3, 2, 1], bwd);
+
+        /* front and back meet in the middle */
+        let mut it = unsafe { nurse.iter() };
+        assert_eq!(3, it.len());
+        assert_eq!(Some(&1), it.next());
+        assert_eq!(Some(&3), it.next_back());
+        assert_eq!(Some(&2), it.next());
+        assert_eq!(None, it.next_back());
+        assert_eq!(None, it.next());
+
+        for data in unsafe { nurse.as_mut().iter_mut() } {
+            *data *= 10;
+        }
+        let fwd: Vec<u32> = unsafe { nurse.iter() }.copied().collect();
+        assert_eq!(vec![10, 20, 30], fwd);
+
+        ll_2.as_mut().unlink();
+        let fwd: Vec<u32> = unsafe { nurse.as_mut().iter_mut() }
+            .map(|data| *data)
+            .collect();
+        assert_eq!(vec![10, 30], fwd);
+    }
+}
@@ -1,122 +1,286 @@
 //! generic Double linked list type
+//! nodes are linked only while pinned,
+//! so the chain never holds a dangling link after a move
+//! `Dll::iter` and `Dll::iter_mut` are unsafe, because a node
+//! does not borrow the other nodes of its chain,
+//! `chain::DllChain` borrows pinned nodes and iterates them safely
 //! do not contain atomic primitives,
 //! so if used in multi-threaded code,
-//! access should be protected by mutex
-//! crate uses default, that have issues in rust
-//! <https://github.com/rust-lang/rust/issues/73014>
-#![feature(default_free_fn)]
-use core::default::default;
-use core::ptr::null_mut;
+//! access should be protected by mutex,
+//! or `sync::SyncDll` should be used
+//! `lru::LruCache` pairs the list with a hash index,
+//! `timer_wheel::TimerWheel` schedules timers in lists
+//! with `serde` feature owning lists are serialized as sequences
+//! owning list nodes can be taken from `pool::NodePool`
+//! `adapter::LinkList` links values through their fields,
+//! so one value can be a member of several lists,
+//! `adapter::LinkChain` borrows them and iterates them safely
+//! builds on stable, `std` feature is on by default,
+//! without it the crate is `no_std` and needs only `alloc`
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+use core::marker::PhantomPinned;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr::{self, null_mut};
+pub mod adapter;
+pub mod algo;
+pub mod arena;
+pub mod chain;
+pub mod cursor;
+pub mod iter;
+pub mod list;
+#[cfg(feature = "std")]
+pub mod lru;
+pub mod pool;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+pub mod sync;
+pub mod timer_wheel;
+use crate::iter::{Iter, IterMut};
+
+/// double linked list links
+///
+/// always placed at the start of the node,
+/// so pointer to the links is pointer to the node
+///
+/// -`next`  - next element, null if unlinked
+/// -`prev`  - prev element, null if unlinked
+#[repr(C)]
+pub(crate) struct Link {
+    next: *mut Link,
+    prev: *mut Link,
+    _pin: PhantomPinned,
+}
+
+impl Link {
+    /// Links ctor
+    /// -`return`  - new unlinked links
+    pub(crate) const fn new() -> Self {
+        Link {
+            next: null_mut(),
+            prev: null_mut(),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Next element in the chain
+    pub(crate) unsafe fn next(this: *const Link) -> *mut Link {
+        (*this).next
+    }
+
+    /// Prev element in the chain
+    pub(crate) unsafe fn prev(this: *const Link) -> *mut Link {
+        (*this).prev
+    }
+
+    /// Link entry to self, if it is not linked yet
+    pub(crate) unsafe fn self_link(this: *mut Link) {
+        if (*this).next.is_null() {
+            (*this).next = this;
+            (*this).prev = this;
+        }
+    }
+
+    /// Insert unlinked node between two adjacent nodes
+    pub(crate) unsafe fn link_between(node: *mut Link, prev: *mut Link, next: *mut Link) {
+        (*node).next = next;
+        (*node).prev = prev;
+        (*prev).next = node;
+        (*next).prev = node;
+    }
+
+    /// Insert chain of nodes between two adjacent nodes
+    /// -`first`   - first node of the chain
+    /// -`last`    - last node of the chain
+    pub(crate) unsafe fn splice_between(
+        first: *mut Link,
+        last: *mut Link,
+        prev: *mut Link,
+        next: *mut Link,
+    ) {
+        (*first).prev = prev;
+        (*last).next = next;
+        (*prev).next = first;
+        (*next).prev = last;
+    }
+
+    /// Take chain of nodes out of its ring, keep the ring linked,
+    /// chain ends are left dangling
+    /// -`first`   - first node of the chain
+    /// -`last`    - last node of the chain
+    pub(crate) unsafe fn cut(first: *mut Link, last: *mut Link) {
+        (*(*first).prev).next = (*last).next;
+        (*(*last).next).prev = (*first).prev;
+    }
+
+    /// Take node out of its chain, keep the chain linked
+    pub(crate) unsafe fn detach(node: *mut Link) {
+        if !(*node).next.is_null() {
+            (*(*node).prev).next = (*node).next;
+            (*(*node).next).prev = (*node).prev;
+            (*node).next = null_mut();
+            (*node).prev = null_mut();
+        }
+    }
+
+    /// Count nodes in the ring, sentinel excluded
+    ///
+    /// -`nurse`   - list sentinel
+    /// -`return`  - nodes count
+    pub(crate) unsafe fn ring_len(nurse: *const Link) -> usize {
+        let mut len: usize = 0;
+        let mut sel: *const Link = (*nurse).next;
+        /* unlinked sentinel is an empty list */
+        while !sel.is_null() && nurse != sel {
+            len += 1;
+            sel = (*sel).next;
+        }
+        len
+    }
+}
 
 /// double linked list element
 ///
-/// -`next`  - next element
-/// -`prev`  - prev element
-/// -`data`  - linked list payload
+/// node is created unlinked, with null links,
+/// so it can be freely moved until it is pinned
+/// and added into a list. Linked node is always pinned,
+/// so the chain can't observe dangling links after a move.
+///
+/// -`link`  - next and prev elements, null if unlinked
+/// -`data`  - linked list payload, not structurally pinned
 #[repr(C)]
 pub struct Dll<T> {
-    pub next: *mut Dll<T>,
-    pub prev: *mut Dll<T>,
+    link: Link,
     pub data: T,
 }
 
-impl<T: Clone + Default> Dll<T> {
+impl<T: Default> Dll<T> {
     /// Double linked list ctor
     /// data field will be zeroed
-    /// -`return`  - new double linked list instance
-    /// care, for complex types must be relinked,
-    /// after higher level ctor
+    /// -`return`  - new unlinked double linked list instance
     pub fn new() -> Self {
-        let mut the_dll: Self = Self {
-            data: default(),
-            next: null_mut(),
-            prev: null_mut(),
-        };
-        /* link entry to self */
-        the_dll.next = &mut the_dll;
-        the_dll.prev = &mut the_dll;
-
-        the_dll
+        Self::from(T::default())
     }
+}
 
+impl<T> Dll<T> {
     /// Double linked list ctor
     /// data field will be taken from arg
     /// -`data`    - data to init
-    /// -`return`  - new double linked list instance
+    /// -`return`  - new unlinked double linked list instance
     pub fn from(init_data: T) -> Self {
-        let mut the_dll: Self = Self {
+        Self {
             data: init_data,
-            /* can't here take address of the_dll */
-            next: null_mut(),
-            prev: null_mut(),
-        };
-        /* link entry to self */
-        the_dll.next = &mut the_dll;
-        the_dll.prev = &mut the_dll;
+            /* links are set, when node is pinned and added */
+            link: Link::new(),
+        }
+    }
+
+    /// Unwrap the payload of unlinked node
+    /// -`return`  - node payload
+    pub fn into_inner(self) -> T {
+        /* node was moved, so it can't be linked */
+        let this: ManuallyDrop<Self> = ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.data) }
+    }
 
-        the_dll
+    /// Return true, if node is a member of some chain
+    pub fn is_linked(&self) -> bool {
+        !self.link.next.is_null()
     }
 
     /// Return true, if linked list node
-    /// is last
+    /// is last, i.e. there are no other nodes in its chain
     ///
-    pub fn is_last(&mut self) -> bool {
-        self.next == self.prev
+    pub fn is_last(&self) -> bool {
+        /* two-element ring also have next == prev,
+         * so compare with self
+         */
+        let this: *const Link = (self as *const Self).cast();
+        self.link.next.is_null() || this == self.link.next
     }
 
-    /// Add node to head
+    /// Get mutable access to the payload,
+    /// payload is not structurally pinned
+    /// -`return`  - node payload
+    pub fn data_mut(self: Pin<&mut Self>) -> &mut T {
+        unsafe { &mut self.get_unchecked_mut().data }
+    }
+
+    /// Add node to head,
+    /// node is unlinked from its previous chain first
     ///
     /// -`nhead`  - node to add into head
-    pub fn addh(&mut self, nhead: &mut Self) {
-        let ohead: *mut Self = self.next;
-
+    pub fn addh(self: Pin<&mut Self>, nhead: Pin<&mut Self>) {
         unsafe {
-            nhead.next = ohead;
-            nhead.prev = self;
-            (*ohead).prev = nhead;
-            self.next = nhead;
+            let this: *mut Link = Self::as_link(self);
+            let node: *mut Link = Self::as_link(nhead);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, this, Link::next(this));
         }
     }
 
-    /// Add node to tail
+    /// Add node to tail,
+    /// node is unlinked from its previous chain first
     ///
     /// -`ntail`    - node to add into tail
-    pub fn addt(&mut self, ntail: &mut Self) {
-        let otail: *mut Self = self.prev;
-
+    pub fn addt(self: Pin<&mut Self>, ntail: Pin<&mut Self>) {
         unsafe {
-            ntail.next = self;
-            ntail.prev = otail;
-            (*otail).next = ntail;
-            self.prev = ntail;
+            let this: *mut Link = Self::as_link(self);
+            let node: *mut Link = Self::as_link(ntail);
+            Link::detach(node);
+            Link::self_link(this);
+            Link::link_between(node, Link::prev(this), this);
         }
     }
 
-    /// Delete node, must be called in drop,
-    /// to keep the chain linked
-    pub fn delete(self) {
+    /// Unlink node
+    pub fn unlink(self: Pin<&mut Self>) {
         unsafe {
-            (*(self.prev)).next = self.next;
-            (*(self.next)).prev = self.prev;
+            Link::detach(Self::as_link(self));
         }
     }
 
-    /// Unlinke node
-    pub fn unlink(&mut self) {
+    /// Iterate over the list, self is treated as sentinel
+    /// and is not yielded
+    /// -`return`  - iterator over nodes payload
+    ///
+    /// # Safety
+    ///
+    /// Other nodes of the chain are not borrowed by the iterator,
+    /// so while it is alive they must not be accessed mutably,
+    /// unlinked or dropped through their own handles.
+    /// Use [`crate::chain::DllChain`] to iterate borrowed nodes safely,
+    /// or [`crate::list::DllList`] for the safe owning list.
+    pub unsafe fn iter(&self) -> Iter<'_, T> {
+        let nurse: *const Link = (self as *const Self).cast();
+        unsafe { Iter::from_raw(nurse, Link::ring_len(nurse)) }
+    }
+
+    /// Mutably iterate over the list, self is treated as sentinel
+    /// and is not yielded
+    /// -`return`  - iterator over nodes payload
+    ///
+    /// # Safety
+    ///
+    /// Other nodes of the chain are not borrowed by the iterator,
+    /// so while it is alive they must not be accessed at all,
+    /// unlinked or dropped through their own handles.
+    /// Use [`crate::chain::DllChain`] to iterate borrowed nodes safely,
+    /// or [`crate::list::DllList`] for the safe owning list.
+    pub unsafe fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T> {
         unsafe {
-            (*(self.prev)).next = self.next;
-            (*(self.next)).prev = self.prev;
-            /* link entry to self */
-            self.next = self;
-            self.prev = self;
+            let nurse: *mut Link = Self::as_link(self);
+            IterMut::from_raw(nurse, Link::ring_len(nurse))
         }
     }
 
-    /// Relink node
-    pub fn relink(&mut self) {
-        /* link entry to self */
-        self.next = self;
-        self.prev = self;
+    /// Links of pinned node, pointer covers the whole node
+    unsafe fn as_link(this: Pin<&mut Self>) -> *mut Link {
+        (this.get_unchecked_mut() as *mut Self).cast()
     }
 }
 
@@ -124,67 +288,76 @@ impl<T> Drop for Dll<T> {
     fn drop(&mut self) {
         /* keep the chain linked */
         unsafe {
-            (*(self.prev)).next = self.next;
-            (*(self.next)).prev = self.prev;
+            Link::detach((self as *mut Self).cast());
         }
     }
 }
 
-/* linked list have special clone rules */
+/* linked list have special clone rules,
+ * clone is not a member of any chain
+ */
 impl<T: Clone> Clone for Dll<T> {
     fn clone(&self) -> Self {
-        let mut new_dll: Self = Self {
-            data: self.data.clone(),
-            /* can't here take address of new_dll */
-            next: null_mut(),
-            prev: null_mut(),
-        };
-        new_dll.next = &mut new_dll;
-        new_dll.prev = &mut new_dll;
-
-        new_dll
+        Self::from(self.data.clone())
     }
 }
 
-impl<T: Clone + Default> Default for Dll<T> {
+impl<T: Default> Default for Dll<T> {
     fn default() -> Self {
-        let mut the_dll: Self = Self {
-            data: default(),
-            next: null_mut(),
-            prev: null_mut(),
-        };
-        /* link entry to self */
-        the_dll.next = &mut the_dll;
-        the_dll.prev = &mut the_dll;
-
-        the_dll
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::Dll;
+    use core::pin::pin;
     #[test]
     fn linked_list_tests() {
         /* generate nurse */
-        let mut nurse: Dll<u32> = Dll::new();
-        nurse.data = 0x00;
+        let mut nurse = pin!(Dll::<u32>::new());
 
         /* generate 4 nums */
-        let mut ll_1: Dll<u32> = Dll::new();
-        ll_1.data = 0x01;
-        let mut ll_2: Dll<u32> = Dll::new();
-        ll_2.data = 0x02;
-        let mut ll_3: Dll<u32> = Dll::new();
-        ll_3.data = 0x03;
-        let mut ll_4: Dll<u32> = Dll::new();
-        ll_4.data = 0x04;
-
-        nurse.addh(&mut ll_1);
-        nurse.addh(&mut ll_2);
-        nurse.addh(&mut ll_3);
-        nurse.addh(&mut ll_4);
-
-        ll_2.unlink();
+        let mut ll_1 = pin!(Dll::from(0x01));
+        let mut ll_2 = pin!(Dll::from(0x02));
+        let mut ll_3 = pin!(Dll::from(0x03));
+        let mut ll_4 = pin!(Dll::from(0x04));
+
+        nurse.as_mut().addh(ll_1.as_mut());
+        nurse.as_mut().addh(ll_2.as_mut());
+        nurse.as_mut().addh(ll_3.as_mut());
+        nurse.as_mut().addh(ll_4.as_mut());
+
+        ll_2.as_mut().unlink();
+        assert!(!ll_2.is_linked());
+        let fwd: Vec<u32> = unsafe { nurse.iter() }.copied().collect();
+        assert_eq!(vec![4, 3, 1], fwd);
+    }
+
+    #[test]
+    fn move_before_link_tests() {
+        /* unlinked nodes may be moved freely */
+        let mut nodes: Vec<Dll<u32>> = (0..4).map(Dll::from).collect();
+        let moved: Dll<u32> = nodes.remove(2);
+        assert!(!moved.is_linked());
+        let cloned: Dll<u32> = moved.clone();
+        assert!(!cloned.is_linked());
+
+        /* pin at final address and link */
+        let mut nurse = Box::pin(Dll::<u32>::default());
+        let mut node = Box::pin(moved);
+        nurse.as_mut().addt(node.as_mut());
+        assert!(nurse.is_linked() && node.is_linked());
+
+        /* boxed sentinel may be moved together with its box */
+        let mut nurses = vec![nurse];
+        let fwd: Vec<u32> = unsafe { nurses[0].iter() }.copied().collect();
+        assert_eq!(vec![2], fwd);
+
+        /* dropped node leaves the chain linked */
+        drop(node);
+        let nurse = nurses.pop().unwrap();
+        assert_eq!(0, unsafe { nurse.iter() }.len());
+        assert!(nurse.is_last());
     }
 }
@@ -0,0 +1,545 @@
+//! Owning double linked list
+//! nodes are taken from the list allocator, so the list
+//! can be moved freely and owns its payload,
+//! the sentinel takes one node of the allocator too
+use crate::cursor::{Cursor, CursorMut};
+use crate::iter::{Iter, IterMut};
+use crate::pool::{AllocError, Global, NodeAlloc};
+use crate::{Dll, Link};
+use alloc::boxed::Box;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+
+/// Owning double linked list
+///
+/// -`nurse`   - sentinel node from the allocator, its payload is never set
+/// -`len`     - nodes count
+/// -`alloc`   - node memory source
+pub struct DllList<T, A: NodeAlloc<T> = Global> {
+    pub(crate) nurse: NonNull<Link>,
+    pub(crate) len: usize,
+    alloc: A,
+    marker: PhantomData<Box<Dll<T>>>,
+}
+
+/* list owns its nodes, like `Box` does */
+unsafe impl<T: Send, A: NodeAlloc<T> + Send> Send for DllList<T, A> {}
+unsafe impl<T: Sync, A: NodeAlloc<T> + Sync> Sync for DllList<T, A> {}
+
+impl<T> DllList<T> {
+    /// List ctor
+    /// -`return`  - new empty list
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: NodeAlloc<T>> DllList<T, A> {
+    /// List ctor, panics, if node memory is exhausted
+    /// -`alloc`   - node memory source, sentinel takes one node
+    /// -`return`  - new empty list
+    pub fn new_in(alloc: A) -> Self {
+        match Self::try_new_in(alloc) {
+            Ok(list) => list,
+            Err(_) => panic!("node memory is exhausted"),
+        }
+    }
+
+    /// List ctor
+    /// -`alloc`   - node memory source, sentinel takes one node
+    /// -`return`  - new empty list, or error with allocator,
+    ///              if node memory is exhausted
+    pub fn try_new_in(alloc: A) -> Result<Self, AllocError<A>> {
+        let nurse: NonNull<Link> = match alloc.alloc() {
+            Some(node) => node.cast(),
+            None => return Err(AllocError(alloc)),
+        };
+        unsafe {
+            /* links are first in the node, payload stays uninit */
+            nurse.as_ptr().write(Link::new());
+            Link::self_link(nurse.as_ptr());
+        }
+        Ok(DllList {
+            nurse,
+            len: 0,
+            alloc,
+            marker: PhantomData,
+        })
+    }
+
+    /// Node memory source
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Nodes count
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true, if list have no nodes
+    pub fn is_empty(&self) -> bool {
+        0 == self.len
+    }
+
+    /// Add data to head, panics, if node memory is exhausted
+    /// -`data`    - data to add
+    pub fn push_front(&mut self, data: T) {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            self.link_between(data, nurse, Link::next(nurse));
+        }
+    }
+
+    /// Add data to tail, panics, if node memory is exhausted
+    /// -`data`    - data to add
+    pub fn push_back(&mut self, data: T) {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            self.link_between(data, Link::prev(nurse), nurse);
+        }
+    }
+
+    /// Add data to head
+    /// -`data`    - data to add
+    /// -`return`  - error with data, if node memory is exhausted
+    pub fn try_push_front(&mut self, data: T) -> Result<(), AllocError<T>> {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            self.try_link_between(data, nurse, Link::next(nurse))
+        }
+    }
+
+    /// Add data to tail
+    /// -`data`    - data to add
+    /// -`return`  - error with data, if node memory is exhausted
+    pub fn try_push_back(&mut self, data: T) -> Result<(), AllocError<T>> {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            self.try_link_between(data, Link::prev(nurse), nurse)
+        }
+    }
+
+    /// Take data from head
+    /// -`return`  - head data, or None, if list is empty
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        unsafe { Some(self.unlink_node(Link::next(self.nurse.as_ptr()))) }
+    }
+
+    /// Take data from tail
+    /// -`return`  - tail data, or None, if list is empty
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        unsafe { Some(self.unlink_node(Link::prev(self.nurse.as_ptr()))) }
+    }
+
+    /// Take data at selected position,
+    /// list is walked from the nearest end
+    /// -`at`      - position from head
+    /// -`return`  - data, or None, if position is out of the list
+    pub fn remove(&mut self, at: usize) -> Option<T> {
+        if at >= self.len {
+            return None;
+        }
+        unsafe { Some(self.unlink_node(self.node_at(at))) }
+    }
+
+    /// Move all nodes of other list to the tail, in O(1),
+    /// lists must share node memory
+    /// -`other`   - list to take nodes from, left empty
+    pub fn append(&mut self, other: &mut DllList<T, A>) {
+        unsafe {
+            let nurse: *mut Link = self.nurse.as_ptr();
+            self.splice_chain(other, Link::prev(nurse), nurse);
+        }
+    }
+
+    /// Split the list in two at selected position,
+    /// list is walked from the nearest end,
+    /// panics, if node memory is exhausted,
+    /// new list takes a node for its sentinel
+    /// -`at`      - position from head, must not exceed `len()`
+    /// -`return`  - list with nodes from `at` to tail
+    pub fn split_off(&mut self, at: usize) -> DllList<T, A> {
+        match self.try_split_off(at) {
+            Ok(list) => list,
+            Err(_) => panic!("node memory is exhausted"),
+        }
+    }
+
+    /// Split the list in two at selected position,
+    /// list is walked from the nearest end
+    /// -`at`      - position from head, must not exceed `len()`
+    /// -`return`  - list with nodes from `at` to tail, or error,
+    ///              if node memory is exhausted, list is left whole
+    pub fn try_split_off(&mut self, at: usize) -> Result<DllList<T, A>, AllocError<()>> {
+        assert!(at <= self.len, "split position is out of the list");
+        if 0 == at {
+            return self.try_take_all();
+        }
+        unsafe {
+            let sel: *mut Link = self.node_at(at - 1);
+            CursorMut::from_raw(self, sel, at - 1).try_split_after()
+        }
+    }
+
+    /// Cursor at head, or at sentinel, if list is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        unsafe { Cursor::from_raw(self, Link::next(self.nurse.as_ptr()), 0) }
+    }
+
+    /// Cursor at tail, or at sentinel, if list is empty
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        let at: usize = self.len.saturating_sub(1);
+        unsafe { Cursor::from_raw(self, Link::prev(self.nurse.as_ptr()), at) }
+    }
+
+    /// Mutable cursor at head, or at sentinel, if list is empty
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, A> {
+        unsafe {
+            let sel: *mut Link = Link::next(self.nurse.as_ptr());
+            CursorMut::from_raw(self, sel, 0)
+        }
+    }
+
+    /// Mutable cursor at tail, or at sentinel, if list is empty
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T, A> {
+        let at: usize = self.len.saturating_sub(1);
+        unsafe {
+            let sel: *mut Link = Link::prev(self.nurse.as_ptr());
+            CursorMut::from_raw(self, sel, at)
+        }
+    }
+
+    /// Drop all nodes
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Head data
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// Tail data
+    pub fn back(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    /// Mutable head data
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.iter_mut().next()
+    }
+
+    /// Mutable tail data
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.iter_mut().next_back()
+    }
+
+    /// Iterate over the list from head to tail
+    pub fn iter(&self) -> Iter<'_, T> {
+        unsafe { Iter::from_raw(self.nurse.as_ptr(), self.len) }
+    }
+
+    /// Mutably iterate over the list from head to tail
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        unsafe { IterMut::from_raw(self.nurse.as_ptr(), self.len) }
+    }
+
+    /*** raw node helpers ***/
+
+    /// Take all nodes into a new list with the same node memory
+    /// -`return`  - error, if node memory is exhausted
+    fn try_take_all(&mut self) -> Result<DllList<T, A>, AllocError<()>> {
+        let empty: DllList<T, A> = match DllList::try_new_in(self.alloc.clone()) {
+            Ok(list) => list,
+            Err(_) => return Err(AllocError(())),
+        };
+        Ok(mem::replace(self, empty))
+    }
+
+    /// Find node at position, walking from the nearest end
+    /// -`at`      - position from head, must be less than `len`
+    unsafe fn node_at(&self, at: usize) -> *mut Link {
+        let nurse: *mut Link = self.nurse.as_ptr();
+        let mut sel: *mut Link;
+        if at < self.len / 2 {
+            sel = Link::next(nurse);
+            for _ in 0..at {
+                sel = Link::next(sel);
+            }
+        } else {
+            sel = Link::prev(nurse);
+            for _ in at + 1..self.len {
+                sel = Link::prev(sel);
+            }
+        }
+        sel
+    }
+
+    /// Cut chain of nodes out of the list into a new list
+    /// -`first`   - first node of the chain
+    /// -`last`    - last node of the chain
+    /// -`count`   - nodes in the chain
+    /// -`return`  - error, if node memory is exhausted,
+    ///              nothing is cut then
+    pub(crate) unsafe fn try_cut_chain(
+        &mut self,
+        first: *mut Link,
+        last: *mut Link,
+        count: usize,
+    ) -> Result<DllList<T, A>, AllocError<()>> {
+        let mut chain: DllList<T, A> = match DllList::try_new_in(self.alloc.clone()) {
+            Ok(list) => list,
+            Err(_) => return Err(AllocError(())),
+        };
+        if 0 != count {
+            let nurse: *mut Link = chain.nurse.as_ptr();
+            Link::cut(first, last);
+            Link::splice_between(first, last, nurse, nurse);
+            self.len -= count;
+            chain.len = count;
+        }
+        Ok(chain)
+    }
+
+    /// Move all nodes of other list between two adjacent nodes
+    /// -`other`   - list to take nodes from, must share node memory,
+    ///              it is left empty
+    pub(crate) unsafe fn splice_chain(
+        &mut self,
+        other: &mut DllList<T, A>,
+        prev: *mut Link,
+        next: *mut Link,
+    ) {
+        assert!(
+            self.alloc.same(&other.alloc),
+            "lists use different node memory"
+        );
+        if !other.is_empty() {
+            let nurse: *mut Link = other.nurse.as_ptr();
+            let first: *mut Link = Link::next(nurse);
+            let last: *mut Link = Link::prev(nurse);
+            Link::cut(first, last);
+            Link::splice_between(first, last, prev, next);
+            self.len += other.len;
+            other.len = 0;
+        }
+    }
+
+    /// Put the data into a new node and insert it between two adjacent nodes,
+    /// panics, if node memory is exhausted
+    pub(crate) unsafe fn link_between(&mut self, data: T, prev: *mut Link, next: *mut Link) {
+        if self.try_link_between(data, prev, next).is_err() {
+            panic!("node memory is exhausted");
+        }
+    }
+
+    /// Put the data into a new node and insert it between two adjacent nodes
+    /// -`return`  - error with data, if node memory is exhausted
+    pub(crate) unsafe fn try_link_between(
+        &mut self,
+        data: T,
+        prev: *mut Link,
+        next: *mut Link,
+    ) -> Result<(), AllocError<T>> {
+        let node: NonNull<Dll<T>> = match self.alloc.alloc() {
+            Some(node) => node,
+            None => return Err(AllocError(data)),
+        };
+        node.as_ptr().write(Dll::from(data));
+        Link::link_between(node.as_ptr().cast(), prev, next);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Take node out of the list and free its memory
+    pub(crate) unsafe fn unlink_node(&mut self, node: *mut Link) -> T {
+        Link::detach(node);
+        self.len -= 1;
+        let node: *mut Dll<T> = node.cast();
+        /* unlinked node needs no drop, only its payload */
+        let data: T = ptr::read(&(*node).data);
+        self.alloc.free(NonNull::new_unchecked(node));
+        data
+    }
+}
+
+impl<T, A: NodeAlloc<T>> Drop for DllList<T, A> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe {
+            /* sentinel payload was never set, so nothing is dropped */
+            self.alloc.free(self.nurse.cast());
+        }
+    }
+}
+
+impl<T, A: NodeAlloc<T> + Default> Default for DllList<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T: Clone, A: NodeAlloc<T>> DllList<T, A> {
+    /// Copy the list, copy shares node memory with the original
+    /// -`return`  - copy, or error, if node memory is exhausted,
+    ///              nodes copied so far are freed then
+    pub fn try_clone(&self) -> Result<Self, AllocError<()>> {
+        let mut list: Self = match Self::try_new_in(self.alloc.clone()) {
+            Ok(list) => list,
+            Err(_) => return Err(AllocError(())),
+        };
+        for data in self.iter() {
+            if list.try_push_back(data.clone()).is_err() {
+                return Err(AllocError(()));
+            }
+        }
+        Ok(list)
+    }
+}
+
+impl<T: Clone, A: NodeAlloc<T>> Clone for DllList<T, A> {
+    /// Copy shares node memory with the original,
+    /// panics, if node memory is exhausted
+    fn clone(&self) -> Self {
+        match self.try_clone() {
+            Ok(list) => list,
+            Err(_) => panic!("node memory is exhausted"),
+        }
+    }
+}
+
+impl<T, A: NodeAlloc<T>> Extend<T> for DllList<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push_back(data);
+        }
+    }
+}
+
+impl<T, A: NodeAlloc<T> + Default> FromIterator<T> for DllList<T, A> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list: Self = Self::default();
+        list.extend(iter);
+        list
+    }
+}
+
+/// Owning double linked list iterator
+pub struct IntoIter<T, A: NodeAlloc<T> = Global> {
+    list: DllList<T, A>,
+}
+
+impl<T, A: NodeAlloc<T>> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, A: NodeAlloc<T>> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, A: NodeAlloc<T>> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: NodeAlloc<T>> FusedIterator for IntoIter<T, A> {}
+
+impl<T, A: NodeAlloc<T>> IntoIterator for DllList<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T, A: NodeAlloc<T>> IntoIterator for &'a DllList<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: NodeAlloc<T>> IntoIterator for &'a mut DllList<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::list::DllList;
+    use std::rc::Rc;
+
+    #[test]
+    fn list_tests() {
+        let mut list: DllList<u32> = DllList::new();
+        assert!(list.is_empty());
+        assert_eq!(None, list.pop_front());
+        assert_eq!(None, list.pop_back());
+
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        list.push_front(0);
+        assert_eq!(4, list.len());
+        assert_eq!(Some(&0), list.front());
+        assert_eq!(Some(&3), list.back());
+
+        /* list is movable, nodes stay in place */
+        let mut moved: Vec<DllList<u32>> = vec![list];
+        let mut list: DllList<u32> = moved.pop().unwrap();
+
+        assert_eq!(Some(2), list.remove(2));
+        assert_eq!(None, list.remove(3));
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(3), list.pop_back());
+        assert_eq!(vec![1], list.iter().copied().collect::<Vec<u32>>());
+
+        list.extend([5, 6, 7]);
+        *list.back_mut().unwrap() += 10;
+        let copy: DllList<u32> = list.clone();
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(
+            vec![17, 6, 5, 1],
+            copy.into_iter().rev().collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    fn list_drop_tests() {
+        /* every node payload is dropped exactly once */
+        let tracker: Rc<()> = Rc::new(());
+        let mut list: DllList<Rc<()>> = (0..8).map(|_| tracker.clone()).collect();
+        assert_eq!(9, Rc::strong_count(&tracker));
+        drop(list.remove(4));
+        drop(list.pop_back());
+        assert_eq!(7, Rc::strong_count(&tracker));
+        let mut it = list.into_iter();
+        drop(it.next());
+        assert_eq!(6, Rc::strong_count(&tracker));
+        drop(it);
+        assert_eq!(1, Rc::strong_count(&tracker));
+    }
+}
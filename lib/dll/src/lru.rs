@@ -0,0 +1,266 @@
+//! Least recently used cache
+//! hash index points straight to the list nodes,
+//! so lookup, move to head and eviction from tail
+//! are all O(1). Head is the most recently used entry.
+use crate::iter::Iter;
+use crate::list::DllList;
+use crate::{Dll, Link};
+use core::borrow::Borrow;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+/// Least recently used cache
+///
+/// -`index`   - key to node of the list
+/// -`list`    - entries, from most to least recently used
+/// -`cap`     - max entries count
+pub struct LruCache<K, V> {
+    index: HashMap<K, *mut Link>,
+    list: DllList<(K, V)>,
+    cap: usize,
+}
+
+/* index points only into the owned list */
+unsafe impl<K: Send, V: Send> Send for LruCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LruCache<K, V> {}
+
+/// Node entry
+unsafe fn entry<'a, K, V>(node: *mut Link) -> &'a mut (K, V) {
+    &mut (*node.cast::<Dll<(K, V)>>()).data
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Cache ctor
+    /// -`cap`     - max entries count, with 0 nothing is cached
+    /// -`return`  - new empty cache
+    pub fn new(cap: usize) -> Self {
+        LruCache {
+            index: HashMap::with_capacity(cap),
+            list: DllList::new(),
+            cap,
+        }
+    }
+
+    /// Entries count
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Return true, if cache have no entries
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Max entries count
+    pub fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Change max entries count, extra entries are evicted
+    /// -`cap`     - new max entries count
+    pub fn set_cap(&mut self, cap: usize) {
+        self.cap = cap;
+        while self.len() > cap {
+            self.pop_lru();
+        }
+    }
+
+    /// Return true, if key is cached, entry is not touched
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Get value and mark it most recently used
+    /// -`key`     - entry key
+    /// -`return`  - value, or None, if key is not cached
+    pub fn get<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get_mut(key).map(|value: &mut V| &*value)
+    }
+
+    /// Get value for modification and mark it most recently used
+    /// -`key`     - entry key
+    /// -`return`  - value, or None, if key is not cached
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let node: *mut Link = *self.index.get(key)?;
+        unsafe {
+            self.touch(node);
+            Some(&mut entry::<K, V>(node).1)
+        }
+    }
+
+    /// Get value, entry is not touched
+    /// -`key`     - entry key
+    /// -`return`  - value, or None, if key is not cached
+    pub fn peek<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let node: *mut Link = *self.index.get(key)?;
+        unsafe { Some(&(*node.cast::<Dll<(K, V)>>()).data.1) }
+    }
+
+    /// Put value and mark it most recently used,
+    /// least recently used entry is dropped, if cache is full
+    /// -`key`     - entry key
+    /// -`value`   - value to put
+    /// -`return`  - replaced value, or None, if key was not cached
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        match self.update(&key, value) {
+            Ok(old) => Some(old),
+            Err(value) => {
+                drop(self.insert(key, value));
+                None
+            }
+        }
+    }
+
+    /// Put value and mark it most recently used
+    /// -`key`     - entry key
+    /// -`value`   - value to put
+    /// -`return`  - replaced entry with the same key,
+    ///              or evicted least recently used entry,
+    ///              or None, if nothing left the cache
+    pub fn push(&mut self, key: K, value: V) -> Option<(K, V)> {
+        match self.update(&key, value) {
+            Ok(old) => Some((key, old)),
+            Err(value) => self.insert(key, value),
+        }
+    }
+
+    /// Take least recently used entry
+    /// -`return`  - entry, or None, if cache is empty
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value): (K, V) = self.list.pop_back()?;
+        self.index.remove(&key);
+        Some((key, value))
+    }
+
+    /// Take entry by key
+    /// -`key`     - entry key
+    /// -`return`  - value, or None, if key is not cached
+    pub fn pop<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let node: *mut Link = self.index.remove(key)?;
+        unsafe { Some(self.list.unlink_node(node).1) }
+    }
+
+    /// Drop all entries
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.list.clear();
+    }
+
+    /// Iterate entries from most to least recently used,
+    /// entries are not touched
+    pub fn iter(&self) -> Iter<'_, (K, V)> {
+        self.list.iter()
+    }
+
+    /// Replace value of cached key and mark it most recently used
+    /// -`return`  - replaced value, or value back, if key is not cached
+    fn update(&mut self, key: &K, value: V) -> Result<V, V> {
+        match self.index.get(key) {
+            Some(&node) => unsafe {
+                self.touch(node);
+                Ok(core::mem::replace(&mut entry::<K, V>(node).1, value))
+            },
+            None => Err(value),
+        }
+    }
+
+    /// Add entry of not cached key to head, evict tail if cache is full
+    /// -`return`  - evicted entry, or None
+    fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.list.push_front((key.clone(), value));
+        unsafe {
+            self.index.insert(key, Link::next(self.list.nurse.as_ptr()));
+        }
+        if self.len() > self.cap {
+            return self.pop_lru();
+        }
+        None
+    }
+
+    /// Move node to head
+    unsafe fn touch(&mut self, node: *mut Link) {
+        let nurse: *mut Link = self.list.nurse.as_ptr();
+        Link::detach(node);
+        Link::link_between(node, nurse, Link::next(nurse));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lru::LruCache;
+
+    fn keys(cache: &LruCache<u32, String>) -> Vec<u32> {
+        cache.iter().map(|(key, _)| *key).collect()
+    }
+
+    #[test]
+    fn lru_tests() {
+        let mut cache: LruCache<u32, String> = LruCache::new(3);
+        assert_eq!(None, cache.put(1, "one".to_string()));
+        assert_eq!(None, cache.put(2, "two".to_string()));
+        assert_eq!(None, cache.put(3, "three".to_string()));
+        assert_eq!(vec![3, 2, 1], keys(&cache));
+
+        /* get touches, peek does not */
+        assert_eq!(Some(&"one".to_string()), cache.get(&1));
+        assert_eq!(Some(&"two".to_string()), cache.peek(&2));
+        assert_eq!(vec![1, 3, 2], keys(&cache));
+
+        cache.get_mut(&3).unwrap().push('!');
+        assert_eq!(Some("three!".to_string()), cache.put(3, "3".to_string()));
+        assert_eq!(Some(&"3".to_string()), cache.peek(&3));
+        assert_eq!(vec![3, 1, 2], keys(&cache));
+
+        assert_eq!(Some("one".to_string()), cache.pop(&1));
+        assert!(!cache.contains(&1));
+        assert_eq!(None, cache.get(&1));
+        assert_eq!(Some((2, "two".to_string())), cache.pop_lru());
+        assert_eq!(1, cache.len());
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(None, cache.pop_lru());
+    }
+
+    #[test]
+    fn lru_eviction_tests() {
+        let mut cache: LruCache<u32, String> = LruCache::new(2);
+        assert_eq!(None, cache.push(1, "one".to_string()));
+        assert_eq!(None, cache.push(2, "two".to_string()));
+        cache.get(&1);
+        /* least recently used entry is evicted */
+        assert_eq!(
+            Some((2, "two".to_string())),
+            cache.push(3, "three".to_string())
+        );
+        assert_eq!(Some((1, "one".to_string())), cache.push(1, "1".to_string()));
+        assert_eq!(vec![1, 3], keys(&cache));
+
+        cache.put(4, "four".to_string());
+        assert_eq!(vec![4, 1], keys(&cache));
+        cache.set_cap(1);
+        assert_eq!(vec![4], keys(&cache));
+
+        /* zero capacity cache keeps nothing */
+        let mut cache: LruCache<u32, String> = LruCache::new(0);
+        assert_eq!(
+            Some((5, "five".to_string())),
+            cache.push(5, "five".to_string())
+        );
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+//! Node memory for owning double linked list
+//! list takes memory for every node from its allocator,
+//! `Global` uses the global allocator, `NodePool` hands out
+//! slots of a fixed capacity pool, that is allocated once.
+//! Every list takes one more node for its sentinel,
+//! so a pool of `n` slots holds `n - 1` nodes of one list.
+use crate::Dll;
+use alloc::alloc::{alloc, dealloc, Layout};
+use alloc::boxed::Box;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+
+/// Node memory source of the owning list
+///
+/// allocator is a cheap handle, list keeps a clone of it
+/// and every list split off gets one more clone
+///
+/// # Safety
+/// memory returned by `alloc` must be valid for `Dll<T>`
+/// until it is passed to `free` of the same allocator,
+/// or of an allocator, for which `same` returns true
+pub unsafe trait NodeAlloc<T>: Clone {
+    /// Take memory for one node
+    /// -`return`  - uninit node memory, or None, if memory is exhausted
+    fn alloc(&self) -> Option<NonNull<Dll<T>>>;
+
+    /// Give node memory back
+    ///
+    /// # Safety
+    /// node must be taken from this allocator,
+    /// its payload must be already dropped or moved out
+    unsafe fn free(&self, node: NonNull<Dll<T>>);
+
+    /// Return true, if nodes of one allocator can be freed by the other,
+    /// only such lists can exchange nodes
+    fn same(&self, other: &Self) -> bool;
+}
+
+/// Global allocator
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Global;
+
+unsafe impl<T> NodeAlloc<T> for Global {
+    fn alloc(&self) -> Option<NonNull<Dll<T>>> {
+        /* node always have links, so it is never zero sized */
+        unsafe { NonNull::new(alloc(Layout::new::<Dll<T>>()).cast()) }
+    }
+
+    unsafe fn free(&self, node: NonNull<Dll<T>>) {
+        dealloc(node.as_ptr().cast(), Layout::new::<Dll<T>>());
+    }
+
+    fn same(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Free pool slot marker
+const NIL: usize = usize::MAX;
+
+/// Fixed capacity node pool, lists borrow it
+///
+/// free slots keep index of the next free slot,
+/// node is always big and aligned enough for it
+///
+/// -`slots`   - node memory
+/// -`free`    - first free slot, `NIL` if pool is exhausted
+/// -`used`    - slots in use
+pub struct NodePool<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<Dll<T>>>]>,
+    free: Cell<usize>,
+    used: Cell<usize>,
+}
+
+impl<T> NodePool<T> {
+    /// Pool ctor, the only allocation of the pool
+    /// -`capacity`  - max nodes count
+    /// -`return`    - new pool with all slots free
+    pub fn new(capacity: usize) -> Self {
+        let pool: NodePool<T> = NodePool {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            free: Cell::new(if 0 == capacity { NIL } else { 0 }),
+            used: Cell::new(0),
+        };
+        for at in 0..capacity {
+            let next: usize = if at + 1 == capacity { NIL } else { at + 1 };
+            unsafe { pool.slot(at).write(next) };
+        }
+        pool
+    }
+
+    /// Max nodes count
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Nodes in use
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Free nodes count
+    pub fn available(&self) -> usize {
+        self.capacity() - self.used()
+    }
+
+    /// Free slot memory, keeps next free slot index
+    fn slot(&self, at: usize) -> *mut usize {
+        self.slots[at].get().cast()
+    }
+}
+
+unsafe impl<T> NodeAlloc<T> for &NodePool<T> {
+    fn alloc(&self) -> Option<NonNull<Dll<T>>> {
+        let at: usize = self.free.get();
+        if NIL == at {
+            return None;
+        }
+        unsafe {
+            self.free.set(self.slot(at).read());
+        }
+        self.used.set(self.used.get() + 1);
+        NonNull::new(self.slots[at].get().cast())
+    }
+
+    unsafe fn free(&self, node: NonNull<Dll<T>>) {
+        let base: *const UnsafeCell<MaybeUninit<Dll<T>>> = self.slots.as_ptr();
+        let at: usize = node
+            .as_ptr()
+            .cast_const()
+            .cast::<UnsafeCell<MaybeUninit<Dll<T>>>>()
+            .offset_from(base) as usize;
+        self.slot(at).write(self.free.get());
+        self.free.set(at);
+        self.used.set(self.used.get() - 1);
+    }
+
+    fn same(&self, other: &Self) -> bool {
+        ptr::eq(*self, *other)
+    }
+}
+
+/// Node memory is exhausted, data is given back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocError<T>(pub T);
+
+impl<T> AllocError<T> {
+    /// Take data back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for AllocError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("node memory is exhausted")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for AllocError<T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::list::DllList;
+    use crate::pool::{AllocError, NodePool};
+    use std::rc::Rc;
+
+    #[test]
+    fn pool_tests() {
+        let pool: NodePool<u32> = NodePool::new(5);
+        let mut list: DllList<u32, &NodePool<u32>> = DllList::new_in(&pool);
+        /* sentinel takes a slot too */
+        assert_eq!(1, pool.used());
+        assert_eq!(Ok(()), list.try_push_back(1));
+        assert_eq!(Ok(()), list.try_push_back(2));
+        assert_eq!(Ok(()), list.try_push_front(0));
+        assert_eq!(1, pool.available());
+        assert_eq!(Ok(()), list.try_push_back(9));
+        assert_eq!(Some(9), list.pop_back());
+        assert_eq!(Ok(()), list.try_push_back(9));
+        /* exhausted pool gives data back */
+        assert_eq!(Err(AllocError(3)), list.try_push_back(3));
+        assert_eq!(4, list.len());
+        assert!(DllList::try_new_in(&pool).is_err());
+
+        /* freed slots are reused, split lists share the pool */
+        assert_eq!(Some(0), list.pop_front());
+        assert_eq!(Some(9), list.pop_back());
+        let mut tail: DllList<u32, &NodePool<u32>> = list.split_off(1);
+        assert_eq!(Ok(()), tail.try_push_back(4));
+        assert!(tail.try_push_back(5).is_err());
+        /* append needs no temporary sentinel */
+        list.append(&mut tail);
+        assert!(list.iter().eq([1, 2, 4].iter()));
+        drop(tail);
+        assert_eq!(4, pool.used());
+        drop(list);
+        assert_eq!(0, pool.used());
+
+        let tiny: NodePool<u32> = NodePool::new(1);
+        let mut list: DllList<u32, &NodePool<u32>> = DllList::new_in(&tiny);
+        assert!(list.try_push_front(0).is_err());
+        let empty: NodePool<u32> = NodePool::new(0);
+        assert!(DllList::<u32, &NodePool<u32>>::try_new_in(&empty).is_err());
+    }
+
+    #[test]
+    fn pool_drop_tests() {
+        let tracker: Rc<()> = Rc::new(());
+        let pool: NodePool<Rc<()>> = NodePool::new(8);
+        {
+            let mut list: DllList<Rc<()>, &NodePool<Rc<()>>> = DllList::new_in(&pool);
+            while list.try_push_back(tracker.clone()).is_ok() {}
+            assert_eq!(8, Rc::strong_count(&tracker));
+            list.retain(|_| false);
+            assert_eq!(1, pool.used());
+            list.extend((0..3).map(|_| tracker.clone()));
+            let copy: DllList<Rc<()>, &NodePool<Rc<()>>> = list.clone();
+            assert_eq!(8, pool.used());
+            drop(copy);
+        }
+        assert_eq!(1, Rc::strong_count(&tracker));
+        assert_eq!(0, pool.used());
+    }
+
+    #[test]
+    fn pool_full_split_tests() {
+        let pool: NodePool<u32> = NodePool::new(4);
+        let mut list: DllList<u32, &NodePool<u32>> = DllList::new_in(&pool);
+        list.extend([0, 1, 2]);
+        assert_eq!(0, pool.available());
+
+        /* new list has no room for its sentinel, list is left whole */
+        assert_eq!(Err(AllocError(())), list.try_split_off(0).map(drop));
+        assert_eq!(Err(AllocError(())), list.try_split_off(2).map(drop));
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert!(cursor.try_split_after().is_err());
+        assert!(cursor.try_split_before().is_err());
+        assert_eq!(Some(1), cursor.index());
+        assert!(list.try_clone().is_err());
+        assert!(list.iter().eq([0, 1, 2].iter()));
+        assert_eq!(3, list.len());
+        assert_eq!(4, pool.used());
+
+        /* freed slot is enough for the split */
+        assert_eq!(Some(2), list.pop_back());
+        let tail: DllList<u32, &NodePool<u32>> = list.try_split_off(1).unwrap();
+        assert!(tail.iter().eq([1].iter()));
+        drop(tail);
+        /* copy gets its sentinel only, partial copy is freed */
+        list.push_back(1);
+        assert!(list.try_clone().is_err());
+        assert_eq!(3, pool.used());
+    }
+
+    #[test]
+    #[should_panic(expected = "node memory is exhausted")]
+    fn pool_full_split_panic_tests() {
+        let pool: NodePool<u32> = NodePool::new(2);
+        let mut list: DllList<u32, &NodePool<u32>> = DllList::new_in(&pool);
+        list.push_back(0);
+        let _ = list.split_off(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "different node memory")]
+    fn pool_mix_tests() {
+        let pool0: NodePool<u32> = NodePool::new(2);
+        let pool1: NodePool<u32> = NodePool::new(2);
+        let mut list0: DllList<u32, &NodePool<u32>> = DllList::new_in(&pool0);
+        let mut list1: DllList<u32, &NodePool<u32>> = DllList::new_in(&pool1);
+        list1.push_back(1);
+        list0.append(&mut list1);
+    }
+}
@@ -0,0 +1,78 @@
+//! Serde support for owning lists
+//! list is serialized as a sequence from head to tail,
+//! sequence is deserialized into a new list node by node,
+//! so links are always rebuilt, never read from the input
+use crate::arena::DllArena;
+use crate::list::DllList;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+impl<T: Serialize> Serialize for DllList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<T: Serialize> Serialize for DllArena<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Sequence visitor, fills the list from head to tail
+///
+/// -`marker`  - list type
+struct SeqVisitor<L> {
+    marker: PhantomData<L>,
+}
+
+/// List, that can be filled from the tail
+trait PushBack<T>: Default {
+    fn push(&mut self, data: T);
+}
+
+impl<T> PushBack<T> for DllList<T> {
+    fn push(&mut self, data: T) {
+        self.push_back(data);
+    }
+}
+
+impl<T> PushBack<T> for DllArena<T> {
+    fn push(&mut self, data: T) {
+        self.push_back(data);
+    }
+}
+
+impl<'de, T: Deserialize<'de>, L: PushBack<T>> Visitor<'de> for SeqVisitor<(L, T)> {
+    type Value = L;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list: L = L::default();
+        while let Some(data) = seq.next_element()? {
+            list.push(data);
+        }
+        Ok(list)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for DllList<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor::<(Self, T)> {
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for DllArena<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor::<(Self, T)> {
+            marker: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,235 @@
+//! Thread-safe double linked list
+//! owning list protected by a reader-writer lock.
+//!
+//! `SyncDll<T>` is `Send` and `Sync` when `T: Send + Sync`,
+//! so it can be shared between threads by reference or `Arc`.
+//! Every single operation is atomic. Iteration runs either
+//! under the read lock (`for_each`, `read`), so it sees
+//! a consistent list and writers wait until it ends, or over
+//! a copy (`snapshot`), so writers are not blocked at all.
+//! Lock poisoning is ignored: a panic in user code can't
+//! break the links, so the list stays usable.
+//! With `--cfg loom` the lock is taken from `loom`,
+//! so `tests/loom.rs` checks every thread interleaving.
+use crate::list::DllList;
+#[cfg(loom)]
+use loom::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::PoisonError;
+#[cfg(not(loom))]
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::vec::Vec;
+
+/// Thread-safe double linked list
+///
+/// -`list`  - owning list under the lock
+pub struct SyncDll<T> {
+    list: RwLock<DllList<T>>,
+}
+
+impl<T> SyncDll<T> {
+    /// List ctor
+    /// -`return`  - new empty list
+    pub fn new() -> Self {
+        SyncDll {
+            list: RwLock::new(DllList::new()),
+        }
+    }
+
+    /// Nodes count at the moment of call
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Return true, if list have no nodes at the moment of call
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Add data to head
+    /// -`data`    - data to add
+    pub fn push_front(&self, data: T) {
+        self.write().push_front(data);
+    }
+
+    /// Add data to tail
+    /// -`data`    - data to add
+    pub fn push_back(&self, data: T) {
+        self.write().push_back(data);
+    }
+
+    /// Take data from head
+    /// -`return`  - head data, or None, if list is empty
+    pub fn pop_front(&self) -> Option<T> {
+        self.write().pop_front()
+    }
+
+    /// Take data from tail
+    /// -`return`  - tail data, or None, if list is empty
+    pub fn pop_back(&self) -> Option<T> {
+        self.write().pop_back()
+    }
+
+    /// Drop all nodes
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+
+    /// Take all nodes at once, list is left empty
+    /// -`return`  - list with all nodes
+    pub fn take(&self) -> DllList<T> {
+        core::mem::take(&mut *self.write())
+    }
+
+    /// Visit every node from head to tail under the read lock,
+    /// writers wait until the visit ends
+    /// -`f`       - visitor
+    pub fn for_each<F: FnMut(&T)>(&self, f: F) {
+        self.read().iter().for_each(f);
+    }
+
+    /// Lock the list for reading,
+    /// to run several operations on a consistent list
+    pub fn read(&self) -> RwLockReadGuard<'_, DllList<T>> {
+        self.list.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the list for writing,
+    /// to run several operations atomically
+    pub fn write(&self) -> RwLockWriteGuard<'_, DllList<T>> {
+        self.list.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Unwrap the list
+    pub fn into_inner(self) -> DllList<T> {
+        self.list
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Clone> SyncDll<T> {
+    /// Copy the list under the read lock,
+    /// copy can be iterated without blocking writers
+    /// -`return`  - list data from head to tail
+    pub fn snapshot(&self) -> Vec<T> {
+        self.read().iter().cloned().collect()
+    }
+}
+
+impl<T> Default for SyncDll<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<DllList<T>> for SyncDll<T> {
+    fn from(list: DllList<T>) -> Self {
+        SyncDll {
+            list: RwLock::new(list),
+        }
+    }
+}
+
+/* loom lock can be used only inside of `loom::model` */
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use crate::sync::SyncDll;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Threads and operations per thread, Miri is much slower
+    const THREADS: usize = if cfg!(miri) { 2 } else { 4 };
+    const OPS: usize = if cfg!(miri) { 16 } else { 2000 };
+
+    fn assert_send_sync<S: Send + Sync>() {}
+
+    #[test]
+    fn sync_send_tests() {
+        assert_send_sync::<SyncDll<u32>>();
+        assert_send_sync::<SyncDll<String>>();
+    }
+
+    #[test]
+    fn sync_stress_tests() {
+        let list: Arc<SyncDll<(usize, usize)>> = Arc::new(SyncDll::new());
+
+        thread::scope(|scope| {
+            /* writers push their own ordered sequences */
+            for id in 0..THREADS {
+                let list = &list;
+                scope.spawn(move || {
+                    for seq in 0..OPS {
+                        if 0 == id % 2 {
+                            list.push_back((id, seq));
+                        } else {
+                            list.push_front((id, seq));
+                        }
+                    }
+                });
+            }
+            /* readers see every writer sequence in order */
+            for _ in 0..THREADS {
+                let list = &list;
+                scope.spawn(move || {
+                    for _ in 0..OPS / 100 + 1 {
+                        let mut last: Vec<Option<usize>> = vec![None; THREADS];
+                        list.for_each(|&(id, seq)| {
+                            /* tail writers ascend, head writers descend */
+                            if let Some(prev) = last[id] {
+                                assert!(if 0 == id % 2 { prev < seq } else { prev > seq });
+                            }
+                            last[id] = Some(seq);
+                        });
+                        assert!(list.snapshot().len() <= THREADS * OPS);
+                    }
+                });
+            }
+        });
+        assert_eq!(THREADS * OPS, list.len());
+
+        /* concurrent consumers take every node exactly once */
+        let taken: Vec<usize> = thread::scope(|scope| {
+            let consumers: Vec<_> = (0..THREADS)
+                .map(|id| {
+                    let list = &list;
+                    scope.spawn(move || {
+                        let mut count: usize = 0;
+                        loop {
+                            let data = if 0 == id % 2 {
+                                list.pop_front()
+                            } else {
+                                list.pop_back()
+                            };
+                            match data {
+                                Some(_) => count += 1,
+                                None => break count,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            consumers.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+        assert_eq!(THREADS * OPS, taken.iter().sum::<usize>());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn sync_poison_tests() {
+        let list: SyncDll<u32> = (0..3).collect::<crate::list::DllList<u32>>().into();
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _guard = list.write();
+                    panic!("writer failed");
+                })
+                .join()
+        });
+        assert!(result.is_err());
+        /* list is still usable after poisoning */
+        list.push_back(3);
+        assert_eq!(vec![0, 1, 2, 3], list.snapshot());
+        assert_eq!(4, list.take().len());
+        assert!(list.into_inner().is_empty());
+    }
+}
@@ -0,0 +1,479 @@
+//! Hierarchical timer wheel
+//! every wheel slot is an owning list, timers are moved
+//! between slots by relinking their nodes, so insert,
+//! cancel and reschedule are O(1) and never reallocate.
+//!
+//! Level `l` slot covers `64^l` ticks, timer is placed
+//! on the lowest level, that can hold its delay,
+//! and cascades down, when lower levels wrap around.
+//! Timeouts longer than the top level are clamped to
+//! the top level and cascaded again, until they fit.
+//!
+//! Time is measured in abstract ticks, wheel sees time
+//! only through `tick(now)`, so any `Clock` can drive it.
+use crate::arena::{DllArena, Handle};
+use crate::list::DllList;
+use crate::{Dll, Link};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::iter::FusedIterator;
+
+/// Slots per level bits
+const BITS: u32 = 6;
+/// Slots per level
+const SLOTS: usize = 1 << BITS;
+/// Levels count, wheel holds delays up to `64^LEVELS` ticks
+const LEVELS: usize = 4;
+/// Slot of the expired timers
+const READY: usize = SLOTS * LEVELS;
+
+/// Timer handle, stale after the timer is expired or cancelled
+pub type TimerId = Handle;
+
+/// Time source of the wheel
+pub trait Clock {
+    /// Current time in ticks
+    fn now(&self) -> u64;
+}
+
+/// Clock moved by hand, for tests and simulation
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    /// Clock ctor
+    /// -`now`     - start time in ticks
+    pub fn new(now: u64) -> Self {
+        ManualClock {
+            now: Cell::new(now),
+        }
+    }
+
+    /// Set current time
+    pub fn set(&self, now: u64) {
+        self.now.set(now);
+    }
+
+    /// Move current time forward
+    /// -`ticks`   - ticks to add
+    pub fn advance(&self, ticks: u64) {
+        self.now.set(self.now.get() + ticks);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+/// Monotonic system clock
+///
+/// -`start`   - time of the tick 0
+/// -`tick`    - tick duration
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: std::time::Instant,
+    tick: std::time::Duration,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    /// Clock ctor, tick 0 is now
+    /// -`tick`    - tick duration, should not be zero
+    pub fn new(tick: std::time::Duration) -> Self {
+        SystemClock {
+            start: std::time::Instant::now(),
+            tick,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        (self.start.elapsed().as_nanos() / self.tick.as_nanos().max(1)) as u64
+    }
+}
+
+/// Timer node payload
+///
+/// -`data`      - user data
+/// -`deadline`  - expiration time
+/// -`slot`      - list, node is linked into
+/// -`id`        - timer handle
+struct Entry<T> {
+    data: T,
+    deadline: u64,
+    slot: usize,
+    id: TimerId,
+}
+
+/// Node entry
+unsafe fn entry<'a, T>(node: *mut Link) -> &'a mut Entry<T> {
+    &mut (*node.cast::<Dll<Entry<T>>>()).data
+}
+
+/// Hierarchical timer wheel
+///
+/// -`slots`   - timer lists of all levels, and expired timers list
+/// -`timers`  - handle to timer node
+/// -`now`     - time of the last tick
+pub struct TimerWheel<T> {
+    slots: Vec<DllList<Entry<T>>>,
+    timers: DllArena<*mut Link>,
+    now: u64,
+}
+
+/* handles point only into the owned slots */
+unsafe impl<T: Send> Send for TimerWheel<T> {}
+unsafe impl<T: Sync> Sync for TimerWheel<T> {}
+
+impl<T> TimerWheel<T> {
+    /// Wheel ctor, time starts at 0
+    /// -`return`  - new empty wheel
+    pub fn new() -> Self {
+        Self::new_at(0)
+    }
+
+    /// Wheel ctor
+    /// -`now`     - start time
+    /// -`return`  - new empty wheel
+    pub fn new_at(now: u64) -> Self {
+        TimerWheel {
+            slots: (0..=READY).map(|_| DllList::new()).collect(),
+            timers: DllArena::new(),
+            now,
+        }
+    }
+
+    /// Time of the last tick
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Timers count, expired but not yet taken included
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Return true, if wheel have no timers
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Add timer, passed deadline expires on the next tick
+    /// -`deadline`  - expiration time
+    /// -`data`      - user data
+    /// -`return`    - timer handle
+    pub fn insert(&mut self, deadline: u64, data: T) -> TimerId {
+        let id: TimerId = self.timers.push_back(core::ptr::null_mut());
+        let slot: usize = self.slot_of(deadline);
+        let list: &mut DllList<Entry<T>> = &mut self.slots[slot];
+        list.push_back(Entry {
+            data,
+            deadline,
+            slot,
+            id,
+        });
+        if let Some(node) = self.timers.get_mut(id) {
+            *node = unsafe { Link::prev(list.nurse.as_ptr()) };
+        }
+        id
+    }
+
+    /// Add timer relative to the last tick
+    /// -`delay`     - ticks to expiration
+    /// -`data`      - user data
+    /// -`return`    - timer handle
+    pub fn insert_after(&mut self, delay: u64, data: T) -> TimerId {
+        self.insert(self.now.saturating_add(delay), data)
+    }
+
+    /// Remove timer
+    /// -`id`      - timer handle
+    /// -`return`  - user data, or None, if handle is stale
+    pub fn cancel(&mut self, id: TimerId) -> Option<T> {
+        let node: *mut Link = self.timers.unlink(id)?;
+        unsafe {
+            let slot: usize = entry::<T>(node).slot;
+            Some(self.slots[slot].unlink_node(node).data)
+        }
+    }
+
+    /// Move timer to new deadline
+    /// -`id`        - timer handle
+    /// -`deadline`  - new expiration time
+    /// -`return`    - true, if timer was moved, false, if handle is stale
+    pub fn reschedule(&mut self, id: TimerId, deadline: u64) -> bool {
+        let node: *mut Link = match self.timers.get(id) {
+            Some(&node) => node,
+            None => return false,
+        };
+        unsafe {
+            entry::<T>(node).deadline = deadline;
+            self.relink(node);
+        }
+        true
+    }
+
+    /// Return true, if timer is not expired and not cancelled
+    pub fn contains(&self, id: TimerId) -> bool {
+        self.timers.contains(id)
+    }
+
+    /// Timer expiration time
+    /// -`id`      - timer handle
+    /// -`return`  - deadline, or None, if handle is stale
+    pub fn deadline(&self, id: TimerId) -> Option<u64> {
+        let node: *mut Link = *self.timers.get(id)?;
+        unsafe { Some((*node.cast::<Dll<Entry<T>>>()).data.deadline) }
+    }
+
+    /// Run the wheel up to selected time,
+    /// time never goes back, earlier time only
+    /// takes timers expired before
+    /// -`now`     - current time
+    /// -`return`  - expired timers, ones not taken before
+    ///              the iterator is dropped stay for the next tick
+    pub fn tick(&mut self, now: u64) -> Expired<'_, T> {
+        while self.now < now {
+            /* ticks before the next event touch only empty slots */
+            self.now = self.next_event().map_or(now, |next: u64| next.min(now));
+            self.step();
+        }
+        Expired { wheel: self }
+    }
+
+    /// Run the wheel up to the clock time
+    /// -`clock`   - time source
+    /// -`return`  - expired timers
+    pub fn poll<C: Clock + ?Sized>(&mut self, clock: &C) -> Expired<'_, T> {
+        self.tick(clock.now())
+    }
+
+    /// Drop all timers, all handles are stale
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.slots.iter_mut().for_each(DllList::clear);
+    }
+
+    /// Process single tick at `self.now`
+    fn step(&mut self) {
+        let now: u64 = self.now;
+        /* higher levels cascade first, they may fill lower slots */
+        for level in (1..LEVELS).rev() {
+            let shift: u32 = BITS * level as u32;
+            if 0 == now & ((1 << shift) - 1) {
+                self.cascade(level * SLOTS + ((now >> shift) as usize & (SLOTS - 1)));
+            }
+        }
+        self.cascade(now as usize & (SLOTS - 1));
+    }
+
+    /// Nearest tick after `self.now`, at which `step` finds timers
+    /// -`return`  - tick, or None, if no timer is pending
+    fn next_event(&self) -> Option<u64> {
+        let mut next: Option<u64> = None;
+        for level in 0..LEVELS {
+            let shift: u32 = BITS * level as u32;
+            /* level slots are visited at multiples of the slot span */
+            for ahead in 1..=SLOTS as u64 {
+                let at: u64 = match ((self.now >> shift) + ahead).checked_mul(1 << shift) {
+                    Some(at) if next.is_none_or(|next: u64| at < next) => at,
+                    _ => break,
+                };
+                if !self.slots[level * SLOTS + ((at >> shift) as usize & (SLOTS - 1))].is_empty() {
+                    next = Some(at);
+                    break;
+                }
+            }
+        }
+        next
+    }
+
+    /// Move all timers of the slot to their new slots
+    fn cascade(&mut self, slot: usize) {
+        unsafe {
+            let nurse: *mut Link = self.slots[slot].nurse.as_ptr();
+            while nurse != Link::next(nurse) {
+                self.relink(Link::next(nurse));
+            }
+        }
+    }
+
+    /// Move node to slot of its deadline
+    unsafe fn relink(&mut self, node: *mut Link) {
+        let entry: &mut Entry<T> = entry::<T>(node);
+        let slot: usize = self.slot_of(entry.deadline);
+        self.slots[entry.slot].len -= 1;
+        Link::detach(node);
+        let list: &mut DllList<Entry<T>> = &mut self.slots[slot];
+        let nurse: *mut Link = list.nurse.as_ptr();
+        Link::link_between(node, Link::prev(nurse), nurse);
+        list.len += 1;
+        entry.slot = slot;
+    }
+
+    /// Slot for the deadline
+    fn slot_of(&self, deadline: u64) -> usize {
+        if deadline <= self.now {
+            return READY;
+        }
+        let span: u64 = 1 << (BITS * LEVELS as u32);
+        /* too long timeout waits at the top level */
+        let deadline: u64 = deadline.min(self.now.saturating_add(span - 1));
+        let delta: u64 = deadline - self.now;
+        let mut level: usize = 0;
+        while delta >> (BITS * (level as u32 + 1)) != 0 {
+            level += 1;
+        }
+        level * SLOTS + ((deadline >> (BITS * level as u32)) as usize & (SLOTS - 1))
+    }
+}
+
+impl<T> Default for TimerWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over expired timers
+///
+/// -`wheel`   - wheel to take timers from
+pub struct Expired<'a, T> {
+    wheel: &'a mut TimerWheel<T>,
+}
+
+impl<'a, T> Iterator for Expired<'a, T> {
+    type Item = (TimerId, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry: Entry<T> = self.wheel.slots[READY].pop_front()?;
+        self.wheel.timers.unlink(entry.id);
+        Some((entry.id, entry.data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len: usize = self.wheel.slots[READY].len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Expired<'a, T> {}
+impl<'a, T> FusedIterator for Expired<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::timer_wheel::{Clock, ManualClock, TimerId, TimerWheel};
+
+    fn expired(wheel: &mut TimerWheel<u64>, now: u64) -> Vec<u64> {
+        wheel.tick(now).map(|(_, data)| data).collect()
+    }
+
+    #[test]
+    fn timer_wheel_tests() {
+        let mut wheel: TimerWheel<u64> = TimerWheel::new();
+        let near: TimerId = wheel.insert(5, 5);
+        wheel.insert(3, 3);
+        wheel.insert(3, 30);
+        let cancelled: TimerId = wheel.insert(4, 4);
+        assert_eq!(4, wheel.len());
+        assert_eq!(Some(5), wheel.deadline(near));
+
+        assert_eq!(Some(4), wheel.cancel(cancelled));
+        assert_eq!(None, wheel.cancel(cancelled));
+        assert!(expired(&mut wheel, 2).is_empty());
+        /* same deadline keeps insertion order */
+        assert_eq!(vec![3, 30], expired(&mut wheel, 4));
+        assert!(wheel.contains(near));
+        assert_eq!(vec![5], expired(&mut wheel, 100));
+        assert!(!wheel.contains(near));
+        assert!(wheel.is_empty());
+
+        /* passed deadline expires on the next tick */
+        wheel.insert(10, 10);
+        assert_eq!(vec![10], expired(&mut wheel, 100));
+
+        /* not taken timers stay for the next tick */
+        wheel.insert_after(1, 1);
+        wheel.insert_after(1, 2);
+        assert_eq!(Some(1), wheel.tick(101).next().map(|(_, data)| data));
+        assert_eq!(1, wheel.len());
+        assert_eq!(vec![2], expired(&mut wheel, 101));
+    }
+
+    #[test]
+    fn timer_wheel_levels_tests() {
+        let mut wheel: TimerWheel<u64> = TimerWheel::new_at(7);
+        /* deadlines on every level and beyond the top one */
+        let deadlines: Vec<u64> = vec![8, 63, 64, 71, 4095, 4096, 4103, 300_000, 20_000_000];
+        for &deadline in deadlines.iter().rev() {
+            wheel.insert(deadline, deadline);
+        }
+        let moved: TimerId = wheel.insert(1_000, 1_000);
+        assert!(wheel.reschedule(moved, 50));
+
+        let mut expect: Vec<u64> = deadlines.clone();
+        expect.insert(1, 50);
+        /* every timer fires exactly at its deadline */
+        for deadline in expect {
+            assert!(expired(&mut wheel, deadline - 1).is_empty());
+            let data: u64 = if 50 == deadline { 1_000 } else { deadline };
+            assert_eq!(vec![data], expired(&mut wheel, deadline));
+        }
+        assert!(wheel.is_empty());
+        assert!(!wheel.reschedule(moved, 60));
+    }
+
+    #[test]
+    fn timer_wheel_clock_tests() {
+        let clock: ManualClock = ManualClock::new(1_000);
+        let mut wheel: TimerWheel<u64> = TimerWheel::new_at(clock.now());
+        for delay in [10, 70, 5_000] {
+            wheel.insert_after(delay, delay);
+        }
+        clock.advance(69);
+        assert_eq!(
+            vec![10],
+            wheel.poll(&clock).map(|(_, data)| data).collect::<Vec<_>>()
+        );
+        clock.advance(1);
+        assert_eq!(
+            vec![70],
+            wheel.poll(&clock).map(|(_, data)| data).collect::<Vec<_>>()
+        );
+        clock.set(1_000_000);
+        assert_eq!(1, wheel.poll(&clock).len());
+        assert_eq!(1_000_000, wheel.now());
+        wheel.insert_after(1, 1);
+        wheel.clear();
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn timer_wheel_far_tests() {
+        /* far timer is reached by jumping over empty ticks,
+        beyond the top level it is still cascaded, Miri is much slower */
+        let far: u64 = if cfg!(miri) { 1 << 28 } else { 1 << 36 };
+        let near: u64 = far >> 6;
+        let mut wheel: TimerWheel<u64> = TimerWheel::new();
+        wheel.insert(far, far);
+        wheel.insert(near, near);
+        assert!(expired(&mut wheel, near - 1).is_empty());
+        assert_eq!(vec![near], expired(&mut wheel, near));
+        assert!(expired(&mut wheel, far - 1).is_empty());
+        assert_eq!(vec![far], expired(&mut wheel, far));
+
+        /* deadlines at the end of time do not overflow */
+        let mut wheel: TimerWheel<u64> = TimerWheel::new_at(u64::MAX - 10);
+        wheel.insert(u64::MAX, 1);
+        wheel.insert_after(5, 2);
+        assert_eq!(vec![2], expired(&mut wheel, u64::MAX - 1));
+        assert_eq!(vec![1], expired(&mut wheel, u64::MAX));
+        assert!(wheel.is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! Loading of the last device ids
+//! device ids are process wide, the last ones
+//! are taken by one test only
+//! run with `cargo test -p dll --features serde`
+#![cfg(feature = "serde")]
+use devices::smartsocket::SmartSocket;
+use devices::{Device, DeviceId, SmartDevice};
+use dll::list::DllList;
+
+#[test]
+fn serde_last_id_tests() {
+    /* near the last id, the stored device still loads */
+    let json: &str = r#"[{"Socket": {"id": 18446744073709551613, "text": "socket0",
+        "enabled": false, "power": 0, "energy": 0}}]"#;
+    let list: DllList<SmartDevice> = serde_json::from_str(json).unwrap();
+    assert_eq!(u64::MAX - 2, list.front().unwrap().id().raw());
+
+    /* one new id is left after it */
+    let socket: SmartSocket = SmartSocket::new("socket1");
+    assert_eq!(u64::MAX - 1, socket.id().raw());
+    assert_eq!(None, DeviceId::try_fresh());
+
+    /* stored device with its id still loads, new id is a load error */
+    let stored: DllList<SmartDevice> = serde_json::from_value(serde_json::json!([
+        {"Thermometer": {"id": 5, "text": "thermometer0", "temp": 0, "unit": "Kelvin"}}
+    ]))
+    .unwrap();
+    assert_eq!(5, stored.front().unwrap().id().raw());
+    let mut value: serde_json::Value = serde_json::to_value(&stored).unwrap();
+    value[0]["Thermometer"]
+        .as_object_mut()
+        .unwrap()
+        .remove("id");
+    assert!(serde_json::from_value::<DllList<SmartDevice>>(value).is_err());
+}
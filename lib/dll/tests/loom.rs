@@ -0,0 +1,58 @@
+//! Model checking of `SyncDll` with loom,
+//! every interleaving of the threads is run:
+//! `RUSTFLAGS="--cfg loom" cargo test -p dll --test loom --release`
+#![cfg(loom)]
+use dll::sync::SyncDll;
+use loom::sync::Arc;
+use loom::thread;
+
+#[test]
+fn loom_push_pop_tests() {
+    loom::model(|| {
+        let list: Arc<SyncDll<u32>> = Arc::new(SyncDll::new());
+        list.push_back(1);
+
+        let writer = {
+            let list = list.clone();
+            thread::spawn(move || {
+                list.push_front(0);
+                list.push_back(2);
+            })
+        };
+        let reader = {
+            let list = list.clone();
+            thread::spawn(move || {
+                /* read lock sees writer pushes whole, in order */
+                let snapshot: Vec<u32> = list.snapshot();
+                assert!([&[1][..], &[0, 1], &[0, 1, 2]].contains(&&snapshot[..]));
+                list.pop_back()
+            })
+        };
+        writer.join().unwrap();
+        let popped: Option<u32> = reader.join().unwrap();
+
+        let mut rest: Vec<u32> = list.snapshot();
+        rest.extend(popped);
+        rest.sort_unstable();
+        assert_eq!(vec![0, 1, 2], rest);
+    });
+}
+
+#[test]
+fn loom_take_tests() {
+    loom::model(|| {
+        let list: Arc<SyncDll<u32>> = Arc::new(SyncDll::new());
+        let consumer = {
+            let list = list.clone();
+            thread::spawn(move || list.take())
+        };
+        list.push_back(1);
+        list.push_back(2);
+        let taken: Vec<u32> = consumer.join().unwrap().iter().copied().collect();
+
+        /* every node is either taken or left, never both */
+        let mut all: Vec<u32> = taken.clone();
+        all.extend(list.snapshot());
+        assert_eq!(vec![1, 2], all);
+    });
+}
@@ -0,0 +1,384 @@
+//! Model based tests for double linked list
+//! every operation sequence is replayed against
+//! a `VecDeque` model and the chains are compared
+//! after each step, in both directions.
+//! Suite is sized to run under Miri:
+//! `cargo +nightly miri test -p dll`
+use dll::arena::{DllArena, Handle};
+use dll::list::DllList;
+use dll::Dll;
+use proptest::prelude::*;
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+/// Nodes available to the intrusive chain tests
+const NODES: usize = 4;
+
+/// Proptest cases, Miri is much slower
+fn config() -> ProptestConfig {
+    ProptestConfig {
+        cases: if cfg!(miri) { 8 } else { 256 },
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    }
+}
+
+/// Intrusive chain operation, argument is node slot
+#[derive(Clone, Copy, Debug)]
+enum DllOp {
+    Addh(usize),
+    Addt(usize),
+    Unlink(usize),
+    Drop(usize),
+}
+
+/// Sentinel, node slots and the model of the chain
+struct DllModel {
+    nurse: Pin<Box<Dll<u32>>>,
+    nodes: Vec<Option<Pin<Box<Dll<u32>>>>>,
+    model: VecDeque<u32>,
+}
+
+impl DllModel {
+    fn new() -> Self {
+        DllModel {
+            nurse: Box::pin(Dll::from(u32::MAX)),
+            nodes: (0..NODES).map(|_| None).collect(),
+            model: VecDeque::new(),
+        }
+    }
+
+    /// Get node from slot, node is created, if slot is empty
+    fn node(nodes: &mut [Option<Pin<Box<Dll<u32>>>>], at: usize) -> Pin<&mut Dll<u32>> {
+        nodes[at]
+            .get_or_insert_with(|| Box::pin(Dll::from(at as u32)))
+            .as_mut()
+    }
+
+    fn apply(&mut self, op: DllOp) {
+        match op {
+            DllOp::Addh(at) => {
+                let node: Pin<&mut Dll<u32>> = Self::node(&mut self.nodes, at);
+                self.nurse.as_mut().addh(node);
+                self.model.retain(|&data| data != at as u32);
+                self.model.push_front(at as u32);
+            }
+            DllOp::Addt(at) => {
+                let node: Pin<&mut Dll<u32>> = Self::node(&mut self.nodes, at);
+                self.nurse.as_mut().addt(node);
+                self.model.retain(|&data| data != at as u32);
+                self.model.push_back(at as u32);
+            }
+            DllOp::Unlink(at) => {
+                Self::node(&mut self.nodes, at).unlink();
+                self.model.retain(|&data| data != at as u32);
+            }
+            DllOp::Drop(at) => {
+                self.nodes[at] = None;
+                self.model.retain(|&data| data != at as u32);
+            }
+        }
+    }
+
+    fn check(&self) {
+        let fwd: Vec<u32> = unsafe { self.nurse.iter() }.copied().collect();
+        let bwd: Vec<u32> = unsafe { self.nurse.iter() }.rev().copied().collect();
+        let model_fwd: Vec<u32> = self.model.iter().copied().collect();
+        let model_bwd: Vec<u32> = self.model.iter().rev().copied().collect();
+        assert_eq!(model_fwd, fwd);
+        assert_eq!(model_bwd, bwd);
+        assert_eq!(self.model.is_empty(), self.nurse.is_last());
+
+        for (at, node) in self.nodes.iter().enumerate() {
+            if let Some(node) = node {
+                let member: bool = self.model.contains(&(at as u32));
+                assert_eq!(member, node.is_linked());
+                assert_eq!(!member, node.is_last());
+            }
+        }
+    }
+}
+
+fn dll_op() -> impl Strategy<Value = DllOp> {
+    (0..4u8, 0..NODES).prop_map(|(op, at)| match op {
+        0 => DllOp::Addh(at),
+        1 => DllOp::Addt(at),
+        2 => DllOp::Unlink(at),
+        _ => DllOp::Drop(at),
+    })
+}
+
+/// Owning list operation
+#[derive(Clone, Copy, Debug)]
+enum ListOp {
+    PushFront(u32),
+    PushBack(u32),
+    PopFront,
+    PopBack,
+    Remove(usize),
+    SplitAppend(usize),
+    CursorInsertBefore(usize, u32),
+    CursorInsertAfter(usize, u32),
+    CursorRemove(usize),
+    Retain(u32),
+    DrainFilter(u32),
+    SortByKey(u32),
+    DedupByKey(u32),
+    Clear,
+}
+
+fn list_op() -> impl Strategy<Value = ListOp> {
+    prop_oneof![
+        4 => any::<u32>().prop_map(ListOp::PushFront),
+        4 => any::<u32>().prop_map(ListOp::PushBack),
+        2 => Just(ListOp::PopFront),
+        2 => Just(ListOp::PopBack),
+        2 => (0..16usize).prop_map(ListOp::Remove),
+        1 => (0..16usize).prop_map(ListOp::SplitAppend),
+        2 => (0..16usize, any::<u32>()).prop_map(|(at, d)| ListOp::CursorInsertBefore(at, d)),
+        2 => (0..16usize, any::<u32>()).prop_map(|(at, d)| ListOp::CursorInsertAfter(at, d)),
+        2 => (0..16usize).prop_map(ListOp::CursorRemove),
+        1 => (1..4u32).prop_map(ListOp::Retain),
+        1 => (1..4u32).prop_map(ListOp::DrainFilter),
+        1 => (1..8u32).prop_map(ListOp::SortByKey),
+        1 => (1..8u32).prop_map(ListOp::DedupByKey),
+        1 => Just(ListOp::Clear),
+    ]
+}
+
+/// Apply operation to the list and to the model
+fn list_apply(list: &mut DllList<u32>, model: &mut VecDeque<u32>, op: ListOp) {
+    match op {
+        ListOp::PushFront(data) => {
+            list.push_front(data);
+            model.push_front(data);
+        }
+        ListOp::PushBack(data) => {
+            list.push_back(data);
+            model.push_back(data);
+        }
+        ListOp::PopFront => assert_eq!(model.pop_front(), list.pop_front()),
+        ListOp::PopBack => assert_eq!(model.pop_back(), list.pop_back()),
+        ListOp::Remove(at) => assert_eq!(model.remove(at), list.remove(at)),
+        ListOp::SplitAppend(at) => {
+            let at: usize = at.min(list.len());
+            let mut tail: DllList<u32> = list.split_off(at);
+            assert_eq!(at, list.len());
+            assert_eq!(model.len() - at, tail.len());
+            list.append(&mut tail);
+            assert!(tail.is_empty());
+        }
+        ListOp::CursorInsertBefore(at, data) => {
+            let at: usize = at % (model.len() + 1);
+            let mut cursor = list.cursor_front_mut();
+            for _ in 0..at {
+                cursor.move_next();
+            }
+            cursor.insert_before(data);
+            assert_eq!(at + 1, cursor.index().unwrap_or(model.len() + 1));
+            model.insert(at, data);
+        }
+        ListOp::CursorInsertAfter(at, data) => {
+            let at: usize = at % (model.len() + 1);
+            let mut cursor = list.cursor_back_mut();
+            for _ in 0..at {
+                cursor.move_prev();
+            }
+            /* cursor walks from tail, sentinel is at `len` steps */
+            let pos: Option<usize> = cursor.index();
+            cursor.insert_after(data);
+            match pos {
+                Some(pos) => model.insert(pos + 1, data),
+                None => model.push_front(data),
+            }
+        }
+        ListOp::CursorRemove(at) => {
+            let at: usize = at % (model.len() + 1);
+            let mut cursor = list.cursor_front_mut();
+            for _ in 0..at {
+                cursor.move_next();
+            }
+            assert_eq!(model.remove(at), cursor.remove_current());
+        }
+        ListOp::Retain(div) => {
+            list.retain(|data| 0 != data % div);
+            model.retain(|data| 0 != data % div);
+        }
+        ListOp::DrainFilter(div) => {
+            let taken: Vec<u32> = list.drain_filter(|data| 0 == *data % div).collect();
+            let expect: Vec<u32> = model
+                .iter()
+                .copied()
+                .filter(|data| 0 == data % div)
+                .collect();
+            assert_eq!(expect, taken);
+            model.retain(|data| 0 != data % div);
+        }
+        ListOp::SortByKey(div) => {
+            /* keys repeat, so sort stability is checked */
+            list.sort_by_key(|data| data % div);
+            model.make_contiguous().sort_by_key(|data| data % div);
+        }
+        ListOp::DedupByKey(div) => {
+            list.dedup_by_key(|data| *data % div);
+            let mut vec: Vec<u32> = model.drain(..).collect();
+            vec.dedup_by_key(|data| *data % div);
+            model.extend(vec);
+        }
+        ListOp::Clear => {
+            list.clear();
+            model.clear();
+        }
+    }
+}
+
+fn list_check(list: &DllList<u32>, model: &VecDeque<u32>) {
+    assert_eq!(model.len(), list.len());
+    assert_eq!(model.front(), list.front());
+    assert_eq!(model.back(), list.back());
+    assert!(list.iter().eq(model.iter()));
+    assert!(list.iter().rev().eq(model.iter().rev()));
+}
+
+/// Arena list operation, handles are picked from all handles
+/// ever returned, so some of them are stale
+#[derive(Clone, Copy, Debug)]
+enum ArenaOp {
+    PushFront,
+    PushBack,
+    PopFront,
+    PopBack,
+    Unlink(usize),
+    MoveFront(usize),
+    MoveBack(usize),
+}
+
+fn arena_op() -> impl Strategy<Value = ArenaOp> {
+    prop_oneof![
+        Just(ArenaOp::PushFront),
+        Just(ArenaOp::PushBack),
+        Just(ArenaOp::PopFront),
+        Just(ArenaOp::PopBack),
+        (0..64usize).prop_map(ArenaOp::Unlink),
+        (0..64usize).prop_map(ArenaOp::MoveFront),
+        (0..64usize).prop_map(ArenaOp::MoveBack),
+    ]
+}
+
+/// Apply operation to the arena and to the model,
+/// data is unique, so it identifies the node in the model
+fn arena_apply(
+    arena: &mut DllArena<u32>,
+    model: &mut VecDeque<u32>,
+    handles: &mut Vec<(Handle, u32)>,
+    op: ArenaOp,
+) {
+    let data: u32 = handles.len() as u32;
+    let pick =
+        |handles: &Vec<(Handle, u32)>, at: usize| handles.get(at % handles.len().max(1)).copied();
+    match op {
+        ArenaOp::PushFront => {
+            handles.push((arena.push_front(data), data));
+            model.push_front(data);
+        }
+        ArenaOp::PushBack => {
+            handles.push((arena.push_back(data), data));
+            model.push_back(data);
+        }
+        ArenaOp::PopFront => assert_eq!(model.pop_front(), arena.pop_front()),
+        ArenaOp::PopBack => assert_eq!(model.pop_back(), arena.pop_back()),
+        ArenaOp::Unlink(at) => {
+            if let Some((handle, data)) = pick(handles, at) {
+                let live: Option<usize> = model.iter().position(|&sel| sel == data);
+                assert_eq!(live.and_then(|at| model.remove(at)), arena.unlink(handle));
+            }
+        }
+        ArenaOp::MoveFront(at) | ArenaOp::MoveBack(at) => {
+            if let Some((handle, data)) = pick(handles, at) {
+                let live: Option<usize> = model.iter().position(|&sel| sel == data);
+                let moved: bool = if let ArenaOp::MoveFront(_) = op {
+                    arena.move_front(handle)
+                } else {
+                    arena.move_back(handle)
+                };
+                assert_eq!(live.is_some(), moved);
+                if let Some(at) = live {
+                    model.remove(at);
+                    if let ArenaOp::MoveFront(_) = op {
+                        model.push_front(data);
+                    } else {
+                        model.push_back(data);
+                    }
+                }
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn dll_matches_model(ops in prop::collection::vec(dll_op(), 0..48)) {
+        let mut chain: DllModel = DllModel::new();
+        for op in ops {
+            chain.apply(op);
+            chain.check();
+        }
+    }
+
+    #[test]
+    fn list_matches_model(ops in prop::collection::vec(list_op(), 0..64)) {
+        let mut list: DllList<u32> = DllList::new();
+        let mut model: VecDeque<u32> = VecDeque::new();
+        for op in ops {
+            list_apply(&mut list, &mut model, op);
+            list_check(&list, &model);
+        }
+        assert!(list.into_iter().eq(model));
+    }
+
+    #[test]
+    fn arena_matches_model(ops in prop::collection::vec(arena_op(), 0..64)) {
+        let mut arena: DllArena<u32> = DllArena::new();
+        let mut model: VecDeque<u32> = VecDeque::new();
+        let mut handles: Vec<(Handle, u32)> = Vec::new();
+        for op in ops {
+            arena_apply(&mut arena, &mut model, &mut handles, op);
+            assert_eq!(model.len(), arena.len());
+            assert!(arena.iter().eq(model.iter()));
+            assert!(arena.iter().rev().eq(model.iter().rev()));
+            for (handle, data) in handles.iter() {
+                assert_eq!(model.contains(data), arena.contains(*handle));
+            }
+        }
+        assert!(arena.into_iter().eq(model));
+    }
+}
+
+/// All operation sequences up to selected depth
+/// on the intrusive chain
+#[test]
+fn dll_exhaustive() {
+    let depth: u32 = if cfg!(miri) { 2 } else { 4 };
+    let ops: Vec<DllOp> = (0..NODES)
+        .flat_map(|at| {
+            [
+                DllOp::Addh(at),
+                DllOp::Addt(at),
+                DllOp::Unlink(at),
+                DllOp::Drop(at),
+            ]
+        })
+        .collect();
+
+    for seq in 0..ops.len().pow(depth) {
+        let mut chain: DllModel = DllModel::new();
+        let mut sel: usize = seq;
+        for _ in 0..depth {
+            chain.apply(ops[sel % ops.len()]);
+            chain.check();
+            sel /= ops.len();
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! One smart device as a member of several lists
+//! room, powered on and alarm lists link the same node
+//! through its own link fields, device is never copied.
+use devices::smartsocket::SmartSocket;
+use devices::{SmartDevice, SmartDeviceAccess};
+use dll::adapter::{Adapter, LinkChain, ListLink};
+use dll::dll_adapter;
+use std::cell::RefCell;
+use std::pin::{pin, Pin};
+
+/// Device with links for every list it can join,
+/// lists share the node, so device is switched through `RefCell`
+struct DevNode {
+    dev: RefCell<SmartDevice>,
+    room: ListLink,
+    powered: ListLink,
+    alarm: ListLink,
+}
+
+dll_adapter!(RoomAdapter = DevNode { room });
+dll_adapter!(PoweredAdapter = DevNode { powered });
+dll_adapter!(AlarmAdapter = DevNode { alarm });
+
+fn socket(name: &str) -> Pin<Box<DevNode>> {
+    Box::pin(DevNode {
+        dev: RefCell::new(SmartDevice::from(SmartSocket::new(name))),
+        room: ListLink::new(),
+        powered: ListLink::new(),
+        alarm: ListLink::new(),
+    })
+}
+
+fn names<A: Adapter<Value = DevNode>>(list: &LinkChain<A>) -> Vec<String> {
+    list.iter().map(|node| node.dev.borrow().name()).collect()
+}
+
+#[test]
+fn multi_list_tests() {
+    let kettle: Pin<Box<DevNode>> = socket("kettle");
+    let heater: Pin<Box<DevNode>> = socket("heater");
+    {
+        let mut room = pin!(LinkChain::<RoomAdapter>::new());
+        let mut powered = pin!(LinkChain::<PoweredAdapter>::new());
+        let mut alarm = pin!(LinkChain::<AlarmAdapter>::new());
+
+        room.as_mut().push_back(kettle.as_ref());
+        room.as_mut().push_back(heater.as_ref());
+        powered.as_mut().push_back(heater.as_ref());
+        powered.as_mut().push_back(kettle.as_ref());
+        alarm.as_mut().push_back(heater.as_ref());
+        assert_eq!(vec!["kettle", "heater"], names(&room));
+        assert_eq!(vec!["heater", "kettle"], names(&powered));
+        assert_eq!(vec!["heater"], names(&alarm));
+
+        /* device is switched through one list, seen through the others */
+        for node in powered.iter() {
+            node.dev.borrow_mut().switch(true);
+        }
+        assert!(room
+            .iter()
+            .all(|node| node.dev.borrow().status().contains("on")));
+
+        /* alarm is cleared, device stays in the room and powered */
+        assert!(alarm.as_mut().remove(&heater));
+        assert!(!alarm.as_mut().remove(&heater));
+        assert!(alarm.is_empty());
+        assert!(RoomAdapter::is_linked(&heater) && PoweredAdapter::is_linked(&heater));
+
+        /* removed device leaves every list it is in */
+        assert!(room.as_mut().remove(&heater));
+        assert!(powered.as_mut().remove(&heater));
+        assert_eq!(vec!["kettle"], names(&room));
+        assert_eq!(vec!["kettle"], names(&powered));
+    }
+    /* dropped lists free the devices */
+    assert!(!RoomAdapter::is_linked(&kettle) && !PoweredAdapter::is_linked(&kettle));
+    assert!(heater.dev.borrow().status().contains("on"));
+}
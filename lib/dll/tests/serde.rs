@@ -0,0 +1,88 @@
+//! Serde round trip tests for owning lists
+//! run with `cargo test -p dll --features serde`
+#![cfg(feature = "serde")]
+use devices::smartsocket::SmartSocket;
+use devices::smartthermometer::SmartThermometer;
+use devices::{SmartDevice, SmartDeviceAccess};
+use dll::arena::DllArena;
+use dll::list::DllList;
+
+/// Room device list
+fn devices() -> DllList<SmartDevice> {
+    let mut socket: SmartSocket = SmartSocket::new("socket0");
+    socket.en();
+    socket.update();
+    let mut thermometer: SmartThermometer = SmartThermometer::new("thermometer0");
+    thermometer.update();
+    [
+        SmartDevice::from(socket),
+        SmartDevice::nodev(),
+        SmartDevice::from(thermometer),
+        SmartDevice::from(SmartSocket::new("socket1")),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn status(list: &DllList<SmartDevice>) -> Vec<String> {
+    list.iter().map(SmartDevice::status).collect()
+}
+
+#[test]
+fn serde_list_tests() {
+    let list: DllList<SmartDevice> = devices();
+    let json: String = serde_json::to_string(&list).unwrap();
+    assert!(json.starts_with('[') && json.ends_with(']'));
+
+    let mut back: DllList<SmartDevice> = serde_json::from_str(&json).unwrap();
+    assert_eq!(list.len(), back.len());
+    assert_eq!(status(&list), status(&back));
+    /* stored device keeps its id */
+    let ids: Vec<devices::DeviceId> = list.iter().map(SmartDevice::id).collect();
+    assert_eq!(ids, back.iter().map(SmartDevice::id).collect::<Vec<_>>());
+    /* links are rebuilt in both directions */
+    let rev: Vec<String> = back.iter().rev().map(SmartDevice::name).collect();
+    assert_eq!(
+        vec!["socket1", "thermometer0", "not device", "socket0"],
+        rev
+    );
+    /* restored hardware takes the stored state */
+    let socket: &mut SmartSocket = back.front_mut().unwrap().downcast_mut().unwrap();
+    socket.update();
+    assert!(socket.enabled);
+    assert!(back.pop_front().is_some());
+    back.push_back(SmartDevice::nodev());
+    assert_eq!(4, back.len());
+
+    /* stored id, that would leave no new ids, is rejected, 0 too */
+    for id in [u64::MAX, u64::MAX - 1, 0] {
+        let mut value: serde_json::Value = serde_json::to_value(&list).unwrap();
+        value[0]["Socket"]["id"] = id.into();
+        assert!(serde_json::from_value::<DllList<SmartDevice>>(value).is_err());
+        let mut value: serde_json::Value = serde_json::to_value(&list).unwrap();
+        value[2]["Thermometer"]["id"] = id.into();
+        assert!(serde_json::from_value::<DllList<SmartDevice>>(value).is_err());
+    }
+
+    let empty: DllList<SmartDevice> = serde_json::from_str("[]").unwrap();
+    assert!(empty.is_empty());
+    assert!(serde_json::from_str::<DllList<u32>>("{\"len\": 1}").is_err());
+    assert!(serde_json::from_str::<DllList<u32>>("[1, \"two\"]").is_err());
+}
+
+#[test]
+fn serde_arena_tests() {
+    let mut arena: DllArena<u32> = (0..5).collect();
+    arena.pop_front();
+    arena.push_front(10);
+    let json: String = serde_json::to_string(&arena).unwrap();
+    assert_eq!("[10,1,2,3,4]", json);
+
+    let back: DllArena<u32> = serde_json::from_str(&json).unwrap();
+    assert!(back.iter().eq(arena.iter()));
+    assert!(back.iter().rev().eq(arena.iter().rev()));
+
+    /* list and arena share the format */
+    let list: DllList<u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&list).unwrap());
+}
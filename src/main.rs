@@ -1,19 +1,40 @@
 //! rust cource part3
 extern crate dll;
-use dll::Dll;
+use dll::cursor::CursorMut;
+use dll::list::DllList;
 extern crate devices;
+use devices::backend::SimBackend;
+use devices::locale::{self, Catalog, MsgKey};
+use devices::radio::{RadioAddr, RadioKind, SimRadio};
+use devices::registry::DeviceRegistry;
 use devices::smartsocket::SmartSocket;
 use devices::smartthermometer::SmartThermometer;
+use devices::DeviceId;
 use devices::SmartDevice;
 use devices::SmartDeviceAccess;
 
+/// Report title, prefix is taken from current locale,
+/// English title is the description only
+/// -`key`      - prefix message key
+/// -`text`     - room or house description
+///
+/// -`return`   - title of the report
+fn title(key: MsgKey, text: &str) -> String {
+    let prefix: String = locale::tr(key);
+    if prefix.is_empty() {
+        text.to_string()
+    } else {
+        format!("{}: {}", prefix, text)
+    }
+}
+
 /// Smart room
 /// -`text`     - smart room description
-/// -`nurse`    - device double linked list sentinel
+/// -`devs`     - devices, owned by the room
 #[repr(C)]
 pub struct SmartRoom {
     text: String,
-    nurse: Dll<SmartDevice>,
+    devs: DllList<SmartDevice>,
 }
 
 impl SmartRoom {
@@ -23,24 +44,29 @@ impl SmartRoom {
     ///
     /// -`return`     - new room instance
     pub fn new(room_text: &str) -> Self {
-        let mut room: SmartRoom = SmartRoom {
+        SmartRoom {
             text: room_text.to_string(),
-            nurse: Dll::new(),
-        };
-
-        /* nurse is complex type and must be relinked
-         * todo: Dll ctor issue, will be reworked later
-         */
-        room.nurse.relink();
-
-        room
+            devs: DllList::new(),
+        }
     }
 
-    /// Add device into the room
+    /// Add device into the room, devices are kept sorted by name
     /// -`dev`      - device to add
-    pub fn dev_add(&mut self, dev: &mut Dll<SmartDevice>) {
-        let nurse: &mut Dll<SmartDevice> = &mut self.nurse;
-        nurse.addh(dev);
+    pub fn dev_add(&mut self, dev: SmartDevice) {
+        /* insert after devices with the same or lower name */
+        let mut cursor: CursorMut<SmartDevice> = self.devs.cursor_front_mut();
+        while cursor
+            .current()
+            .is_some_and(|cur: &mut SmartDevice| cur.name_str() <= dev.name_str())
+        {
+            cursor.move_next();
+        }
+        cursor.insert_before(dev);
+    }
+
+    /// Drop all not devices from the room
+    pub fn dev_clean(&mut self) {
+        self.devs.retain(|dev: &SmartDevice| !dev.is_nodev());
     }
 
     /// Find device by name
@@ -48,93 +74,62 @@ impl SmartRoom {
     ///
     /// -`return`       - first finded device reference, or None, if device not present
     pub fn dev_find(&mut self, dev_name: &str) -> Option<&mut SmartDevice> {
-        let nurse: &mut Dll<SmartDevice> = &mut self.nurse;
-        let mut sel: *mut Dll<SmartDevice> = nurse.next;
-        let nurse_addr: *mut Dll<SmartDevice> = nurse;
-        unsafe {
-            while nurse_addr != sel {
-                /* get device */
-                let dev: &SmartDevice = &((*sel).data);
-                /* get info from device */
-                let sel_dev_name: &String = &dev.name();
-
-                if dev_name.eq(sel_dev_name) {
-                    /* device with such name found */
-                    return Some(&mut ((*sel).data));
-                }
-
-                sel = (*sel).next;
-            }
-        }
-        /* device with such name not found */
-        Option::None
+        self.devs
+            .find_mut(|dev: &SmartDevice| dev_name == dev.name_str())
+    }
+
+    /// Find device by id
+    /// -`id`           - device id
+    ///
+    /// -`return`       - device reference, or None, if device not present
+    pub fn dev_get(&mut self, id: DeviceId) -> Option<&mut SmartDevice> {
+        self.devs.find_mut(|dev: &SmartDevice| id == dev.id())
     }
 
     /// Room info request
     /// -`return`       - smart room full information  
     pub fn info(&self) -> String {
-        let nurse: &Dll<SmartDevice> = &self.nurse;
-        let mut rinfo: String = self.text.to_string();
+        let mut rinfo: String = title(MsgKey::LabelRoom, &self.text);
         /* newline for a while will be a delimiter */
         let delm: &'static str = "\n";
         rinfo.push_str(delm);
 
         /* for every device print device info */
-        let mut sel: *mut Dll<SmartDevice> = nurse.next;
-        let nurse_addr: *const Dll<SmartDevice> = nurse;
-
-        unsafe {
-            while nurse_addr != sel {
-                /* get device */
-                let dev: &SmartDevice = &((*sel).data);
-                /* get info from device */
-                let dev_info: String = dev.status();
-
-                rinfo.push_str(&dev_info);
-                rinfo.push_str(delm);
-                sel = (*sel).next;
-            }
+        for dev in self.devs.iter() {
+            rinfo.push_str(&dev.status());
+            rinfo.push_str(delm);
         }
         rinfo
     }
 }
 
-/* linked list have special clone rules */
 impl Clone for SmartRoom {
+    /// Cloned room have copies of all devices,
+    /// copies take new ids
     fn clone(&self) -> Self {
-        let mut new_dll: Self = Self {
+        Self {
             text: self.text.clone(),
-            nurse: self.nurse.clone(),
-        };
-
-        /* relink smart room anyway */
-        new_dll.nurse.relink();
-
-        new_dll
+            devs: self.devs.clone(),
+        }
     }
 }
 
 impl Default for SmartRoom {
     fn default() -> Self {
-        let mut the_dll: Self = Self {
+        Self {
             text: "Room".to_string(),
-            nurse: Dll::default(),
-        };
-
-        /* relink the data anyway */
-        the_dll.nurse.relink();
-
-        the_dll
+            devs: DllList::default(),
+        }
     }
 }
 
 /// Smart house
 /// -`text`     - smart house description
-/// -`nurse`    - room double linked list sentinel
+/// -`rooms`    - rooms, owned by the house
 #[repr(C)]
 struct SmartHouse {
     text: String,
-    nurse: Dll<SmartRoom>,
+    rooms: DllList<SmartRoom>,
 }
 
 impl SmartHouse {
@@ -144,24 +139,24 @@ impl SmartHouse {
     ///
     /// -`return`     - new house instance
     pub fn new(house_text: &str) -> Self {
-        let mut house: SmartHouse = SmartHouse {
+        SmartHouse {
             text: house_text.to_string(),
-            nurse: Dll::new(),
-        };
-
-        /* nurse is complex type and must be relinked
-         * todo: Dll ctor issue, will be reworked later
-         */
-        house.nurse.relink();
-
-        house
+            rooms: DllList::new(),
+        }
     }
 
-    /// Add room into the house
-    /// -`room`      - device to add
-    pub fn room_add(&mut self, room: &mut Dll<SmartRoom>) {
-        let nurse: &mut Dll<SmartRoom> = &mut self.nurse;
-        nurse.addh(room);
+    /// Add room into the house, rooms are kept sorted by name
+    /// -`room`      - room to add
+    pub fn room_add(&mut self, room: SmartRoom) {
+        /* insert after rooms with the same or lower name */
+        let mut cursor: CursorMut<SmartRoom> = self.rooms.cursor_front_mut();
+        while cursor
+            .current()
+            .is_some_and(|cur: &mut SmartRoom| cur.text <= room.text)
+        {
+            cursor.move_next();
+        }
+        cursor.insert_before(room);
     }
 
     /// Find device by name
@@ -169,26 +164,8 @@ impl SmartHouse {
     ///
     /// -`return`       - first finded device reference, or None, if device not present
     pub fn room_find(&mut self, room_name: &str) -> Option<&mut SmartRoom> {
-        let nurse: &mut Dll<SmartRoom> = &mut self.nurse;
-        let mut sel: *mut Dll<SmartRoom> = nurse.next;
-        let nurse_addr: *mut Dll<SmartRoom> = nurse;
-        unsafe {
-            while nurse_addr != sel {
-                /* get device */
-                let room: &SmartRoom = &((*sel).data);
-                /* get info from device */
-                let sel_room_name: &String = &room.text;
-
-                if room_name.eq(sel_room_name) {
-                    /* device with such name found */
-                    return Some(&mut ((*sel).data));
-                }
-
-                sel = (*sel).next;
-            }
-        }
-        /* device with such name not found */
-        Option::None
+        self.rooms
+            .find_mut(|room: &SmartRoom| room_name.eq(&room.text))
     }
 
     /// Find device with selected name in selected room
@@ -207,39 +184,47 @@ impl SmartHouse {
         Option::None
     }
 
-    /// Room info request
-    /// -`return`       - smart room full information  
+    /// Find device by id in any room
+    /// -`id`           - device id
+    ///
+    /// -`return`       - device reference, or None, if device not present
+    pub fn dev_get(&mut self, id: DeviceId) -> Option<&mut SmartDevice> {
+        self.rooms
+            .iter_mut()
+            .find_map(|room: &mut SmartRoom| room.dev_get(id))
+    }
+
+    /// House info request
+    /// -`return`       - smart house full information, text of current locale
     pub fn info(&self) -> String {
-        let nurse: &Dll<SmartRoom> = &self.nurse;
-        let mut rinfo: String = self.text.to_string();
+        let mut rinfo: String = title(MsgKey::LabelHouse, &self.text);
         /* newline for a while will be a delimiter */
         let delm: &'static str = "\n";
         rinfo.push_str(delm);
 
-        /* for every device print device info */
-        let mut sel: *mut Dll<SmartRoom> = nurse.next;
-        let nurse_addr: *const Dll<SmartRoom> = nurse;
-
-        unsafe {
-            while nurse_addr != sel {
-                /* get device */
-                let room: &SmartRoom = &((*sel).data);
-                /* get info from device */
-                let room_info: String = room.info();
-
-                rinfo.push_str(&room_info);
-                rinfo.push_str(delm);
-                sel = (*sel).next;
-            }
+        /* for every room print room info */
+        for room in self.rooms.iter() {
+            rinfo.push_str(&room.info());
+            rinfo.push_str(delm);
         }
         rinfo
     }
 }
 
 /// Task3 main routine
+/// first argument selects the locale,
+/// it is built-in locale name, like "ru", or catalog file path
 fn main() {
     println!("Task3 start\n");
 
+    if let Some(arg) = std::env::args().nth(1) {
+        let catalog = Catalog::builtin(&arg).or_else(|_| Catalog::load(&arg));
+        match catalog {
+            Ok(catalog) => locale::set_locale(catalog),
+            Err(err) => println!("locale {} is not selected: {}", arg, err),
+        }
+    }
+
     println!(
         "Generate devices\n
              every room will have one thermometer\n
@@ -253,10 +238,12 @@ fn main() {
     smartsocket1.en();
     let smartthermometer0: SmartThermometer = SmartThermometer::new("thermometer0");
     let smartthermometer1: SmartThermometer = SmartThermometer::new("thermometer1");
-    let device0: SmartDevice = SmartDevice::Socket(smartsocket0);
-    let device1: SmartDevice = SmartDevice::Thermometer(smartthermometer0);
-    let device2: SmartDevice = SmartDevice::Socket(smartsocket1);
-    let device3: SmartDevice = SmartDevice::Thermometer(smartthermometer1);
+    let device0: SmartDevice = SmartDevice::from(smartsocket0);
+    let device1: SmartDevice = SmartDevice::from(smartthermometer0);
+    let device2: SmartDevice = SmartDevice::from(smartsocket1);
+    let device3: SmartDevice = SmartDevice::from(smartthermometer1);
+    /* devices are found by id after they are moved */
+    let id0: DeviceId = device0.id();
 
     let name0: String = device0.name();
     let stat0: String = device0.status();
@@ -278,38 +265,38 @@ fn main() {
 
     /* generate rooms with devices */
     println!("\nGenerate rooms: room0 and room1");
-    /* wrap devices into double linked list */
-    let mut dev0: Dll<SmartDevice> = Dll::from(device0);
-    let mut dev1: Dll<SmartDevice> = Dll::from(device1);
-    let mut dev2: Dll<SmartDevice> = Dll::from(device2);
-    let mut dev3: Dll<SmartDevice> = Dll::from(device3);
-
-    /* room 0 have device0 and device 1 */
-    let room0: SmartRoom = SmartRoom::new("room0");
-    /* wrap room into linked list */
-    let mut rm0: Dll<SmartRoom> = Dll::from(room0);
-    /* this relinks need due invesed house construction
-     * and will be fixed in future Dll releases
+    /* room 0 have device0 and device 1,
+     * devices are moved into the room
      */
-    rm0.data.nurse.relink();
-
-    /* get hangle back, for easy access */
-    let room0: &mut SmartRoom = &mut rm0.data;
-    room0.dev_add(&mut dev0);
-    room0.dev_add(&mut dev1);
-
-    let room1: SmartRoom = SmartRoom::new("room1");
-    /* wrap into linked list */
-    let mut rm1: Dll<SmartRoom> = Dll::from(room1);
-    /* this relinks need due invesed house construction
-     * and will be fixed in future Dll releases
+    let mut room0: SmartRoom = SmartRoom::new("room0");
+    room0.dev_add(device0);
+    room0.dev_add(device1);
+    /* not device is added by mistake and cleaned out */
+    room0.dev_add(SmartDevice::nodev());
+    room0.dev_clean();
+
+    let mut room1: SmartRoom = SmartRoom::new("room1");
+    room1.dev_add(device2);
+    room1.dev_add(device3);
+
+    /* one more socket is paired over simulated radio network,
+     * it is unpaired, when the room is dropped
      */
-    rm1.data.nurse.relink();
-
-    /* get hangle back, for easy access */
-    let room1: &mut SmartRoom = &mut rm1.data;
-    room1.dev_add(&mut dev2);
-    room1.dev_add(&mut dev3);
+    let radio: SimRadio = SimRadio::new().with_device(
+        RadioAddr(0x0a1b2c3d4e5f),
+        RadioKind::Socket,
+        SimBackend::new(7),
+    );
+    let registry: DeviceRegistry = DeviceRegistry::new(radio);
+    let paired: Option<SmartDevice> = registry.discover().ok().and_then(|adverts| {
+        let addr: RadioAddr = adverts.first()?.addr;
+        registry.pair(addr).ok()?;
+        registry.commission(addr, "socket2").ok()
+    });
+    match paired {
+        Some(dev) => room1.dev_add(dev),
+        None => println!("socket2 is not paired"),
+    }
 
     /* print room information */
     let room0_info: String = room0.info();
@@ -330,8 +317,8 @@ fn main() {
     /* generate house */
     println!("Generate houses: house0");
     let mut house0: SmartHouse = SmartHouse::new("House0");
-    house0.room_add(&mut rm0);
-    house0.room_add(&mut rm1);
+    house0.room_add(room0);
+    house0.room_add(room1);
 
     /* print house info */
     let house_info: String = house0.info();
@@ -353,5 +340,18 @@ fn main() {
         println!("socket0 not found in room0");
     }
 
+    /* copy of the room has devices with the same names, but new ids */
+    let mut room0_copy: SmartRoom = house0
+        .room_find("room0")
+        .map(|room| room.clone())
+        .unwrap_or_default();
+    room0_copy.text = "room0 copy".to_string();
+    house0.room_add(room0_copy);
+    if let Some(dev) = house0.dev_get(id0) {
+        println!("device {} is {}", id0, dev.name());
+    } else {
+        println!("device {} not found", id0);
+    }
+
     println!("\nTask3 done\n");
 }